@@ -0,0 +1,76 @@
+//! Compares the single-pass escaping engine with the chained `.replace` calls
+//! it replaced, on small, large and escape-heavy inputs.
+//!
+//! Run with `cargo bench --bench escaping`.
+
+use std::{
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+use quot::{format_literal, QuoteFormat};
+
+/// How long each case is measured for
+const MEASUREMENT_TIME: Duration = Duration::from_millis(500);
+
+/// The implementation `format_literal` used before the escaping engine
+fn chained_replace(input: &str) -> String {
+    let escaped = input
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+        .replace('\t', "\\t");
+    format!("\"{escaped}\"")
+}
+
+fn single_pass(input: &str) -> String {
+    format_literal(input, QuoteFormat::Double)
+}
+
+/// Average time per call, after a warm-up call
+fn measure(input: &str, escape: fn(&str) -> String) -> Duration {
+    black_box(escape(black_box(input)));
+
+    let mut iterations = 0u32;
+    let start = Instant::now();
+    while start.elapsed() < MEASUREMENT_TIME {
+        black_box(escape(black_box(input)));
+        iterations += 1;
+    }
+    start.elapsed() / iterations
+}
+
+fn throughput(bytes: usize, per_call: Duration) -> f64 {
+    bytes as f64 / per_call.as_secs_f64() / 1_000_000.0
+}
+
+fn main() {
+    let prose = "The quick brown fox jumps over the lazy dog. ".repeat(20);
+    let cases = [
+        ("small", "Hello \"world\"\n".to_string()),
+        ("large", format!("{prose}\n").repeat(5_000)),
+        (
+            "escape-heavy",
+            "{\"key\":\"a\\\\b\",\n\t\"quote\":\"\\\"\"}\r\n".repeat(25_000),
+        ),
+    ];
+
+    println!(
+        "{:<14} {:>10} {:>14} {:>14} {:>9}",
+        "input", "bytes", "replace chain", "single pass", "speedup"
+    );
+    for (name, input) in &cases {
+        assert_eq!(single_pass(input), chained_replace(input));
+
+        let before = measure(input, chained_replace);
+        let after = measure(input, single_pass);
+        println!(
+            "{name:<14} {:>10} {:>9.1} MB/s {:>9.1} MB/s {:>8.1}x",
+            input.len(),
+            throughput(input.len(), before),
+            throughput(input.len(), after),
+            before.as_secs_f64() / after.as_secs_f64()
+        );
+    }
+}
@@ -0,0 +1,109 @@
+use crate::{format_literal, indent, Language, QuoteFormat};
+
+/// Split text into lines, optionally keeping each line's trailing `\n`
+pub fn split_lines(input: &str, keep_newlines: bool) -> Vec<String> {
+    if keep_newlines {
+        input.split_inclusive('\n').map(str::to_string).collect()
+    } else {
+        input.lines().map(str::to_string).collect()
+    }
+}
+
+/// Drop lines that are empty or contain only whitespace
+pub fn remove_blank_lines(lines: Vec<String>) -> Vec<String> {
+    lines
+        .into_iter()
+        .filter(|line| !line.trim().is_empty())
+        .collect()
+}
+
+/// Render lines as an array or list literal with one escaped element per line.
+/// Declared arrays use a form that is valid in a constant initializer.
+pub fn format_array(
+    lines: &[String],
+    quote_format: QuoteFormat,
+    language: Language,
+    declared: bool,
+) -> String {
+    let (open, close) = array_delimiters(language, declared);
+    if lines.is_empty() {
+        return format!("{open}{close}");
+    }
+
+    let indent = indent(language);
+
+    let mut output = format!("{open}\n");
+    for line in lines {
+        let literal = format_literal(line, quote_format);
+        output.push_str(&format!("{indent}{literal},\n"));
+    }
+    output.push_str(close);
+    output
+}
+
+fn array_delimiters(language: Language, declared: bool) -> (&'static str, &'static str) {
+    match language {
+        Language::Rust if declared => ("&[", "]"),
+        Language::Rust => ("vec![", "]"),
+        Language::Python | Language::JavaScript | Language::TypeScript => ("[", "]"),
+        Language::Go => ("[]string{", "}"),
+        Language::Java => ("new String[]{", "}"),
+        Language::C | Language::Cpp | Language::Lua => ("{", "}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_lines() {
+        let lines = split_lines("one\ntwo\r\n\nthree\n", false);
+        assert_eq!(lines, ["one", "two", "", "three"]);
+
+        let lines = split_lines("one\ntwo\n\nthree", true);
+        assert_eq!(lines, ["one\n", "two\n", "\n", "three"]);
+    }
+
+    #[test]
+    fn test_remove_blank_lines() {
+        let lines = vec!["a".to_string(), "".to_string(), "  \t".to_string()];
+        assert_eq!(remove_blank_lines(lines), ["a"]);
+    }
+
+    #[test]
+    fn test_format_array_per_language() {
+        let lines = vec!["a\"b".to_string(), "c".to_string()];
+
+        let rust = format_array(&lines, QuoteFormat::Double, Language::Rust, false);
+        assert_eq!(rust, "vec![\n    \"a\\\"b\",\n    \"c\",\n]");
+
+        let go = format_array(&lines, QuoteFormat::Double, Language::Go, false);
+        assert_eq!(go, "[]string{\n\t\"a\\\"b\",\n\t\"c\",\n}");
+
+        let java = format_array(&lines, QuoteFormat::Double, Language::Java, false);
+        assert!(java.starts_with("new String[]{\n"));
+
+        let python = format_array(&lines, QuoteFormat::Single, Language::Python, false);
+        assert_eq!(python, "[\n    'a\"b',\n    'c',\n]");
+    }
+
+    #[test]
+    fn test_declared_rust_array_is_a_slice() {
+        let lines = vec!["a".to_string()];
+        let rust = format_array(&lines, QuoteFormat::Double, Language::Rust, true);
+        assert_eq!(rust, "&[\n    \"a\",\n]");
+    }
+
+    #[test]
+    fn test_format_empty_array() {
+        assert_eq!(
+            format_array(&[], QuoteFormat::Double, Language::Rust, false),
+            "vec![]"
+        );
+        assert_eq!(
+            format_array(&[], QuoteFormat::Double, Language::Go, false),
+            "[]string{}"
+        );
+    }
+}
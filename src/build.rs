@@ -0,0 +1,266 @@
+//! Helpers for `build.rs` scripts that embed asset files at compile time.
+//!
+//! ```no_run
+//! // In build.rs
+//! quot::build::Builder::new()
+//!     .input("sql")
+//!     .write("sql.rs")
+//!     .expect("failed to embed SQL files");
+//! ```
+//!
+//! The generated file is then pulled in with
+//! `include!(concat!(env!("OUT_DIR"), "/sql.rs"));`.
+
+use std::{
+    env,
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    check_quote_format, file_handler, identifier,
+    module_generator::{self, Entry},
+    renderer::{self, RenderOptions},
+    Language, NameCase, OutputMode, QuoteFormat,
+};
+
+/// Collects asset inputs and renders them into a single generated source file
+#[derive(Debug, Clone)]
+pub struct Builder {
+    inputs: Vec<String>,
+    options: RenderOptions,
+    /// Set by `format` or `options`; otherwise the language decides
+    format: Option<QuoteFormat>,
+}
+
+impl Default for Builder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Builder {
+    /// Start with raw-string Rust constants, the most common shape for embedded
+    /// assets. Other languages default to double quotes, as they have no raw strings.
+    pub fn new() -> Self {
+        Builder {
+            inputs: Vec::new(),
+            options: RenderOptions::default(),
+            format: None,
+        }
+    }
+
+    /// Add a file, a directory (walked recursively) or a glob pattern
+    pub fn input(mut self, path: impl Into<String>) -> Self {
+        self.inputs.push(path.into());
+        self
+    }
+
+    pub fn format(mut self, format: QuoteFormat) -> Self {
+        self.format = Some(format);
+        self
+    }
+
+    pub fn output(mut self, output: OutputMode) -> Self {
+        self.options.output = output;
+        self
+    }
+
+    pub fn lang(mut self, lang: Language) -> Self {
+        self.options.lang = lang;
+        self
+    }
+
+    /// Replace every render setting at once, for options without a dedicated setter
+    pub fn options(mut self, options: RenderOptions) -> Self {
+        self.format = Some(options.format);
+        self.options = options;
+        self
+    }
+
+    /// Render all inputs, one declaration per file, sorted by relative path
+    pub fn generate(&self) -> Result<String, Box<dyn Error>> {
+        let entries = module_generator::collect_entries(&self.inputs)?;
+        self.render_entries(&entries)
+    }
+
+    /// Write the generated source into `$OUT_DIR` and tell Cargo when to rerun
+    pub fn write(&self, file_name: impl AsRef<Path>) -> Result<PathBuf, Box<dyn Error>> {
+        let out_dir =
+            env::var_os("OUT_DIR").ok_or("OUT_DIR is not set; call this from build.rs")?;
+        let dest = Path::new(&out_dir).join(file_name);
+        self.write_to(&dest)?;
+        Ok(dest)
+    }
+
+    /// Write the generated source to an explicit path and print `cargo:rerun-if-changed` lines
+    pub fn write_to(&self, dest: impl AsRef<Path>) -> Result<(), Box<dyn Error>> {
+        let entries = module_generator::collect_entries(&self.inputs)?;
+        for path in rerun_paths(&self.inputs, &entries) {
+            println!("cargo:rerun-if-changed={path}");
+        }
+
+        fs::write(dest, self.render_entries(&entries)?)?;
+        Ok(())
+    }
+
+    fn render_entries(&self, entries: &[Entry]) -> Result<String, Box<dyn Error>> {
+        let case = self
+            .options
+            .name_case
+            .unwrap_or_else(|| identifier::default_case(self.options.lang));
+        let names = module_generator::constant_names(entries.iter().map(|e| e.key.as_str()), case);
+
+        let format = self.format.unwrap_or(match self.options.lang {
+            Language::Rust => QuoteFormat::Raw,
+            Language::C
+            | Language::Cpp
+            | Language::Go
+            | Language::Python
+            | Language::JavaScript
+            | Language::TypeScript
+            | Language::Java
+            | Language::Lua => QuoteFormat::Double,
+        });
+        // Byte arrays hold no string literals, so any format goes with them
+        if !matches!(self.options.output, OutputMode::Bytes) {
+            check_quote_format(format, self.options.lang)?;
+        }
+
+        // Names are already unique identifiers, so keep them exactly as they are
+        let options = RenderOptions {
+            format,
+            name_case: Some(NameCase::Preserve),
+            ..self.options.clone()
+        };
+
+        let comment = line_comment(self.options.lang);
+        let mut source = format!("{comment} @generated by quot. Do not edit by hand.\n");
+        for (entry, name) in entries.iter().zip(&names) {
+            let content = file_handler::read_file_bytes(&entry.path)?;
+            let declaration = renderer::render(&content, Some(name), &options)
+                .map_err(|e| format!("Error rendering '{}': {e}", entry.path.display()))?;
+            source.push_str(&format!("\n{comment} {}\n{declaration}\n", entry.key));
+        }
+        Ok(source)
+    }
+}
+
+/// Directories are watched as a whole so new files trigger a rebuild;
+/// glob patterns can only be watched through the files they matched
+fn rerun_paths(inputs: &[String], entries: &[Entry]) -> Vec<String> {
+    let mut paths: Vec<String> = inputs
+        .iter()
+        .filter(|input| Path::new(input).exists())
+        .cloned()
+        .collect();
+    paths.extend(entries.iter().map(|e| e.path.display().to_string()));
+    paths.sort();
+    paths.dedup();
+    paths
+}
+
+fn line_comment(language: Language) -> &'static str {
+    match language {
+        Language::Python => "#",
+        Language::Lua => "--",
+        Language::Rust
+        | Language::C
+        | Language::Cpp
+        | Language::Go
+        | Language::JavaScript
+        | Language::TypeScript
+        | Language::Java => "//",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn asset_dir(name: &str) -> PathBuf {
+        let root = env::temp_dir().join(name);
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("nested")).unwrap();
+        fs::write(root.join("hello.txt"), "Hello \"world\"").unwrap();
+        fs::write(root.join("nested").join("bye.txt"), "Bye").unwrap();
+        root
+    }
+
+    #[test]
+    fn test_generate_rust_constants() {
+        let root = asset_dir("quot_build_rust_test");
+        let source = Builder::new()
+            .input(root.to_string_lossy())
+            .generate()
+            .unwrap();
+
+        assert_eq!(
+            source,
+            "// @generated by quot. Do not edit by hand.\n\
+             \n// hello.txt\npub const HELLO_TXT: &str = r##\"Hello \"world\"\"##;\n\
+             \n// nested/bye.txt\npub const NESTED_BYE_TXT: &str = r#\"Bye\"#;\n"
+        );
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_generate_other_targets() {
+        let root = asset_dir("quot_build_c_test");
+        let source = Builder::new()
+            .input(root.join("nested").to_string_lossy())
+            .output(OutputMode::Bytes)
+            .lang(Language::C)
+            .generate()
+            .unwrap();
+
+        assert!(source.contains("unsigned char BYE_TXT[] = {\n    0x42, 0x79, 0x65,\n};"));
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_default_format_follows_the_language() {
+        let root = asset_dir("quot_build_default_format_test");
+        let source = Builder::new()
+            .input(root.join("nested").to_string_lossy())
+            .lang(Language::C)
+            .generate()
+            .unwrap();
+        assert!(source.contains("static const char BYE_TXT[] = \"Bye\";"));
+
+        let raw = Builder::new()
+            .input(root.join("nested").to_string_lossy())
+            .format(QuoteFormat::Raw)
+            .lang(Language::C)
+            .generate();
+        assert!(raw.is_err());
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_write_to_and_rerun_paths() {
+        let root = asset_dir("quot_build_write_test");
+        let input = root.to_string_lossy().into_owned();
+        let dest = root.join("generated.rs");
+
+        let builder = Builder::new().input(&input);
+        builder.write_to(&dest).unwrap();
+        assert!(fs::read_to_string(&dest).unwrap().contains("HELLO_TXT"));
+
+        let inputs = vec![input.clone()];
+        let entries = module_generator::collect_entries(&inputs).unwrap();
+        let paths = rerun_paths(&inputs, &entries);
+        assert!(paths.contains(&input));
+        assert!(paths.iter().any(|p| p.ends_with("bye.txt")));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_write_requires_out_dir() {
+        if env::var_os("OUT_DIR").is_none() {
+            assert!(Builder::new().write("assets.rs").is_err());
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use std::error::Error;
+
+use crate::{indent, Language};
+
+/// Render bytes as an `xxd -i` style array declaration for the target language
+pub fn format_byte_array(
+    bytes: &[u8],
+    language: Language,
+    name: &str,
+    bytes_per_line: usize,
+) -> Result<String, Box<dyn Error>> {
+    let indent = indent(language);
+    let body = format_byte_rows(bytes, indent, bytes_per_line);
+    let len = bytes.len();
+    // Match the length constant's suffix to the case of the array name
+    let len_name = if name.chars().any(|c| c.is_ascii_lowercase()) {
+        format!("{name}_len")
+    } else {
+        format!("{name}_LEN")
+    };
+
+    let declaration = match language {
+        Language::Rust => format!("pub static {name}: [u8; {len}] = [\n{body}];"),
+        Language::C | Language::Cpp => {
+            format!("unsigned char {name}[] = {{\n{body}}};\nunsigned int {len_name} = {len};")
+        }
+        Language::Go => format!("var {name} = []byte{{\n{body}}}"),
+        Language::Python
+        | Language::JavaScript
+        | Language::TypeScript
+        | Language::Java
+        | Language::Lua => {
+            return Err(format!("byte array output is not supported for {language}").into());
+        }
+    };
+    Ok(declaration)
+}
+
+fn format_byte_rows(bytes: &[u8], indent: &str, bytes_per_line: usize) -> String {
+    // A zero width would never make progress, so treat it as one byte per line
+    let bytes_per_line = bytes_per_line.max(1);
+
+    let mut rows = String::new();
+    for chunk in bytes.chunks(bytes_per_line) {
+        let row: Vec<String> = chunk.iter().map(|b| format!("0x{b:02x}")).collect();
+        rows.push_str(indent);
+        rows.push_str(&row.join(", "));
+        rows.push_str(",\n");
+    }
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rust_byte_array() {
+        let output = format_byte_array(b"Hi\n", Language::Rust, "DATA", 12).unwrap();
+        assert_eq!(
+            output,
+            "pub static DATA: [u8; 3] = [\n    0x48, 0x69, 0x0a,\n];"
+        );
+    }
+
+    #[test]
+    fn test_c_byte_array_has_length_constant() {
+        let output = format_byte_array(&[0x00, 0xff], Language::C, "data", 12).unwrap();
+        assert_eq!(
+            output,
+            "unsigned char data[] = {\n    0x00, 0xff,\n};\nunsigned int data_len = 2;"
+        );
+    }
+
+    #[test]
+    fn test_c_length_constant_follows_name_case() {
+        let output = format_byte_array(b"a", Language::C, "DATA", 12).unwrap();
+        assert!(output.ends_with("unsigned int DATA_LEN = 1;"));
+    }
+
+    #[test]
+    fn test_go_byte_array_uses_tabs() {
+        let output = format_byte_array(b"ab", Language::Go, "data", 12).unwrap();
+        assert_eq!(output, "var data = []byte{\n\t0x61, 0x62,\n}");
+    }
+
+    #[test]
+    fn test_bytes_per_line() {
+        let output = format_byte_array(b"abcde", Language::Rust, "DATA", 2).unwrap();
+        let rows: Vec<&str> = output.lines().skip(1).take(3).collect();
+        assert_eq!(rows, ["    0x61, 0x62,", "    0x63, 0x64,", "    0x65,"]);
+
+        // Zero is clamped rather than looping forever
+        let output = format_byte_array(b"ab", Language::Rust, "DATA", 0).unwrap();
+        assert_eq!(output.lines().count(), 4);
+    }
+
+    #[test]
+    fn test_unsupported_language() {
+        assert!(format_byte_array(b"ab", Language::Python, "data", 12).is_err());
+    }
+
+    #[test]
+    fn test_empty_input() {
+        let output = format_byte_array(b"", Language::Rust, "DATA", 12).unwrap();
+        assert_eq!(output, "pub static DATA: [u8; 0] = [\n];");
+    }
+}
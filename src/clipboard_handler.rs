@@ -1,11 +1,450 @@
 use arboard::Clipboard;
-use std::error::Error;
+use clap::ValueEnum;
+use std::{cell::OnceCell, env, error::Error, path::Path};
 
-/// Read text content from the system clipboard
-pub fn read_clipboard_input() -> Result<String, Box<dyn Error>> {
+use crate::{command_clipboard, osc52_handler};
+
+/// Hidden subcommand the CLI re-executes itself with to keep serving the
+/// clipboard on X11/Wayland after the main process has exited
+pub const SERVE_CLIPBOARD_COMMAND: &str = "serve-clipboard";
+
+/// Which selection to read or write
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Selection {
+    /// The clipboard filled by an explicit copy
+    Clipboard,
+    /// The X11/Wayland PRIMARY selection filled by selecting text and pasted with a middle click
+    Primary,
+}
+
+/// Which of the formats a copy is offered in to read
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ClipboardFlavor {
+    /// The plain-text version of the copy
+    #[value(name = "text/plain", alias = "plain")]
+    Plain,
+    /// The HTML markup browsers and editors put on the clipboard alongside the text
+    #[value(name = "text/html", alias = "html")]
+    Html,
+}
+
+/// A source and sink for clipboard text
+pub trait ClipboardProvider {
+    fn name(&self) -> &'static str;
+    fn read_text(&mut self, selection: Selection) -> Result<String, Box<dyn Error>>;
+    fn write_text(&mut self, text: &str, selection: Selection) -> Result<(), Box<dyn Error>>;
+
+    /// Read the text/html flavour of the selection
+    fn read_html(&mut self, selection: Selection) -> Result<String, Box<dyn Error>> {
+        let _ = selection;
+        Err(format!("the {} clipboard backend cannot read HTML", self.name()).into())
+    }
+
+    /// Reads are answered on the terminal's input, in between the user's keys
+    fn reads_from_terminal(&self) -> bool {
+        false
+    }
+}
+
+/// Read the selection in the given flavour
+pub fn read_flavor(
+    clipboard: &mut dyn ClipboardProvider,
+    selection: Selection,
+    flavor: ClipboardFlavor,
+) -> Result<String, Box<dyn Error>> {
+    match flavor {
+        ClipboardFlavor::Plain => clipboard.read_text(selection),
+        ClipboardFlavor::Html => clipboard.read_html(selection),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ClipboardBackend {
+    /// Pick the best available provider for this session (default)
+    Auto,
+    /// System clipboard through the display server
+    System,
+    /// wl-copy/wl-paste (Wayland)
+    WlClipboard,
+    /// xclip (X11)
+    Xclip,
+    /// xsel (X11)
+    Xsel,
+    /// pbcopy/pbpaste (macOS)
+    Pasteboard,
+    /// clip.exe and PowerShell's Get-Clipboard (WSL)
+    Wsl,
+    /// Terminal clipboard through OSC 52 escape sequences (works over SSH)
+    Osc52,
+}
+
+/// Create the provider for a backend, resolving `Auto` for the current session
+pub fn create_provider(backend: ClipboardBackend) -> Box<dyn ClipboardProvider> {
+    match backend {
+        ClipboardBackend::Auto => auto_provider(),
+        ClipboardBackend::System => Box::new(ArboardProvider),
+        ClipboardBackend::WlClipboard => Box::new(command_clipboard::WL_CLIPBOARD),
+        ClipboardBackend::Xclip => Box::new(command_clipboard::XCLIP),
+        ClipboardBackend::Xsel => Box::new(command_clipboard::XSEL),
+        ClipboardBackend::Pasteboard => Box::new(command_clipboard::PASTEBOARD),
+        ClipboardBackend::Wsl => Box::new(command_clipboard::WSL),
+        ClipboardBackend::Osc52 => Box::new(Osc52Provider),
+    }
+}
+
+/// The provider for a backend, created the first time the clipboard is used.
+/// Choosing one can mean connecting to the display server and searching
+/// `PATH`, which runs that never touch the clipboard shouldn't pay for.
+pub struct LazyProvider {
+    backend: ClipboardBackend,
+    provider: OnceCell<Box<dyn ClipboardProvider>>,
+}
+
+impl LazyProvider {
+    pub fn new(backend: ClipboardBackend) -> Self {
+        LazyProvider {
+            backend,
+            provider: OnceCell::new(),
+        }
+    }
+
+    fn provider(&mut self) -> &mut dyn ClipboardProvider {
+        let backend = self.backend;
+        self.provider.get_or_init(|| create_provider(backend));
+        self.provider.get_mut().expect("initialised above").as_mut()
+    }
+}
+
+impl ClipboardProvider for LazyProvider {
+    fn name(&self) -> &'static str {
+        self.provider
+            .get_or_init(|| create_provider(self.backend))
+            .name()
+    }
+
+    fn read_text(&mut self, selection: Selection) -> Result<String, Box<dyn Error>> {
+        self.provider().read_text(selection)
+    }
+
+    fn write_text(&mut self, text: &str, selection: Selection) -> Result<(), Box<dyn Error>> {
+        self.provider().write_text(text, selection)
+    }
+
+    fn read_html(&mut self, selection: Selection) -> Result<String, Box<dyn Error>> {
+        self.provider().read_html(selection)
+    }
+
+    fn reads_from_terminal(&self) -> bool {
+        self.provider
+            .get_or_init(|| create_provider(self.backend))
+            .reads_from_terminal()
+    }
+}
+
+fn auto_provider() -> Box<dyn ClipboardProvider> {
+    if !has_display_server() {
+        if is_wsl() && command_clipboard::WSL.is_available() {
+            return Box::new(command_clipboard::WSL);
+        }
+        return Box::new(Osc52Provider);
+    }
+
+    // Fall back to the command-line tools if arboard cannot talk to the display server
+    if is_x11_or_wayland() && Clipboard::new().is_err() {
+        let wayland = env::var_os("WAYLAND_DISPLAY").is_some();
+        let fallbacks = [
+            command_clipboard::WL_CLIPBOARD,
+            command_clipboard::XCLIP,
+            command_clipboard::XSEL,
+        ];
+        if let Some(provider) = fallbacks
+            .into_iter()
+            .filter(|p| wayland || p.name() != command_clipboard::WL_CLIPBOARD.name())
+            .find(command_clipboard::CommandProvider::is_available)
+        {
+            return Box::new(provider);
+        }
+    }
+    Box::new(ArboardProvider)
+}
+
+fn is_x11_or_wayland() -> bool {
+    cfg!(all(
+        unix,
+        not(any(
+            target_os = "macos",
+            target_os = "android",
+            target_os = "emscripten"
+        ))
+    ))
+}
+
+/// Whether a display server is reachable; SSH sessions, containers and
+/// consoles on X11/Wayland platforms usually have neither variable set
+fn has_display_server() -> bool {
+    if is_x11_or_wayland() {
+        ["DISPLAY", "WAYLAND_DISPLAY"]
+            .iter()
+            .any(|var| env::var_os(var).is_some_and(|value| !value.is_empty()))
+    } else {
+        true
+    }
+}
+
+fn is_wsl() -> bool {
+    env::var_os("WSL_DISTRO_NAME").is_some()
+        || Path::new("/proc/sys/fs/binfmt_misc/WSLInterop").exists()
+}
+
+/// The system clipboard through arboard
+pub struct ArboardProvider;
+
+impl ClipboardProvider for ArboardProvider {
+    fn name(&self) -> &'static str {
+        "system"
+    }
+
+    #[cfg(all(
+        unix,
+        not(any(target_os = "macos", target_os = "android", target_os = "emscripten"))
+    ))]
+    fn read_text(&mut self, selection: Selection) -> Result<String, Box<dyn Error>> {
+        use arboard::GetExtLinux;
+
+        let mut clipboard = Clipboard::new()?;
+        let content = clipboard.get().clipboard(linux_kind(selection)).text()?;
+        Ok(content)
+    }
+
+    #[cfg(all(
+        unix,
+        not(any(target_os = "macos", target_os = "android", target_os = "emscripten"))
+    ))]
+    fn read_html(&mut self, selection: Selection) -> Result<String, Box<dyn Error>> {
+        use arboard::GetExtLinux;
+
+        let mut clipboard = Clipboard::new()?;
+        let content = clipboard.get().clipboard(linux_kind(selection)).html()?;
+        Ok(content)
+    }
+
+    #[cfg(not(all(
+        unix,
+        not(any(target_os = "macos", target_os = "android", target_os = "emscripten"))
+    )))]
+    fn read_html(&mut self, selection: Selection) -> Result<String, Box<dyn Error>> {
+        require_clipboard(selection)?;
+        let mut clipboard = Clipboard::new()?;
+        let content = clipboard.get().html()?;
+        Ok(content)
+    }
+
+    #[cfg(not(all(
+        unix,
+        not(any(target_os = "macos", target_os = "android", target_os = "emscripten"))
+    )))]
+    fn read_text(&mut self, selection: Selection) -> Result<String, Box<dyn Error>> {
+        require_clipboard(selection)?;
+        let mut clipboard = Clipboard::new()?;
+        let content = clipboard.get_text()?;
+        Ok(content)
+    }
+
+    #[cfg(all(
+        unix,
+        not(any(target_os = "macos", target_os = "android", target_os = "emscripten"))
+    ))]
+    fn write_text(&mut self, text: &str, selection: Selection) -> Result<(), Box<dyn Error>> {
+        use std::{
+            io::Write,
+            os::unix::process::CommandExt,
+            process::{Command, Stdio},
+        };
+
+        // Fail here, where the error can still be reported, if there is no display server
+        drop(Clipboard::new()?);
+
+        // On X11/Wayland the clipboard is served by its owner, so the contents would
+        // vanish when we exit; hand them to a detached copy of ourselves instead
+        let mut command = Command::new(env::current_exe()?);
+        command.arg(SERVE_CLIPBOARD_COMMAND);
+        if selection == Selection::Primary {
+            command.arg("--primary");
+        }
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .process_group(0)
+            .spawn()?;
+
+        let mut stdin = child
+            .stdin
+            .take()
+            .ok_or("failed to open clipboard server input")?;
+        stdin.write_all(text.as_bytes())?;
+        Ok(())
+    }
+
+    #[cfg(not(all(
+        unix,
+        not(any(target_os = "macos", target_os = "android", target_os = "emscripten"))
+    )))]
+    fn write_text(&mut self, text: &str, selection: Selection) -> Result<(), Box<dyn Error>> {
+        require_clipboard(selection)?;
+        // macOS and Windows keep the contents after the owning process exits
+        let mut clipboard = Clipboard::new()?;
+        clipboard.set_text(text)?;
+        Ok(())
+    }
+}
+
+#[cfg(all(
+    unix,
+    not(any(target_os = "macos", target_os = "android", target_os = "emscripten"))
+))]
+fn linux_kind(selection: Selection) -> arboard::LinuxClipboardKind {
+    match selection {
+        Selection::Clipboard => arboard::LinuxClipboardKind::Clipboard,
+        Selection::Primary => arboard::LinuxClipboardKind::Primary,
+    }
+}
+
+#[cfg(not(all(
+    unix,
+    not(any(target_os = "macos", target_os = "android", target_os = "emscripten"))
+)))]
+fn require_clipboard(selection: Selection) -> Result<(), Box<dyn Error>> {
+    match selection {
+        Selection::Clipboard => Ok(()),
+        Selection::Primary => Err("the PRIMARY selection only exists on X11 and Wayland".into()),
+    }
+}
+
+/// Own the selection with the given text until another application takes it over
+pub fn serve_clipboard(text: String, selection: Selection) -> Result<(), Box<dyn Error>> {
     let mut clipboard = Clipboard::new()?;
-    let content = clipboard.get_text()?;
-    Ok(content)
+
+    #[cfg(all(
+        unix,
+        not(any(target_os = "macos", target_os = "android", target_os = "emscripten"))
+    ))]
+    {
+        use arboard::SetExtLinux;
+        clipboard
+            .set()
+            .clipboard(linux_kind(selection))
+            .wait()
+            .text(text)?;
+    }
+
+    #[cfg(not(all(
+        unix,
+        not(any(target_os = "macos", target_os = "android", target_os = "emscripten"))
+    )))]
+    {
+        require_clipboard(selection)?;
+        clipboard.set_text(text)?;
+    }
+
+    Ok(())
+}
+
+/// The local terminal's clipboard through OSC 52
+pub struct Osc52Provider;
+
+impl ClipboardProvider for Osc52Provider {
+    fn name(&self) -> &'static str {
+        "osc52"
+    }
+
+    fn read_text(&mut self, selection: Selection) -> Result<String, Box<dyn Error>> {
+        osc52_handler::read_clipboard(selection)
+    }
+
+    fn write_text(&mut self, text: &str, selection: Selection) -> Result<(), Box<dyn Error>> {
+        osc52_handler::write_clipboard(text, selection)
+    }
+
+    fn reads_from_terminal(&self) -> bool {
+        true
+    }
+}
+
+/// A clipboard that only lives in memory, so the clipboard flow can be tested
+#[cfg(test)]
+#[derive(Debug, Default)]
+pub struct MemoryProvider {
+    clipboard: Option<String>,
+    primary: Option<String>,
+    html: Option<String>,
+}
+
+#[cfg(test)]
+impl MemoryProvider {
+    pub fn new(contents: &str) -> Self {
+        MemoryProvider {
+            clipboard: Some(contents.to_string()),
+            primary: None,
+            html: None,
+        }
+    }
+
+    pub fn with_primary(contents: &str) -> Self {
+        MemoryProvider {
+            clipboard: None,
+            primary: Some(contents.to_string()),
+            html: None,
+        }
+    }
+
+    /// A copy offered both as plain text and as HTML
+    pub fn with_html(text: &str, html: &str) -> Self {
+        MemoryProvider {
+            clipboard: Some(text.to_string()),
+            primary: None,
+            html: Some(html.to_string()),
+        }
+    }
+
+    pub fn contents(&self, selection: Selection) -> Option<&str> {
+        match selection {
+            Selection::Clipboard => self.clipboard.as_deref(),
+            Selection::Primary => self.primary.as_deref(),
+        }
+    }
+}
+
+#[cfg(test)]
+impl ClipboardProvider for MemoryProvider {
+    fn name(&self) -> &'static str {
+        "memory"
+    }
+
+    fn read_text(&mut self, selection: Selection) -> Result<String, Box<dyn Error>> {
+        self.contents(selection)
+            .map(str::to_string)
+            .ok_or_else(|| "the clipboard is empty".into())
+    }
+
+    fn write_text(&mut self, text: &str, selection: Selection) -> Result<(), Box<dyn Error>> {
+        let contents = match selection {
+            Selection::Clipboard => &mut self.clipboard,
+            Selection::Primary => &mut self.primary,
+        };
+        *contents = Some(text.to_string());
+        Ok(())
+    }
+
+    fn read_html(&mut self, selection: Selection) -> Result<String, Box<dyn Error>> {
+        match selection {
+            Selection::Clipboard => self
+                .html
+                .clone()
+                .ok_or_else(|| "no HTML on the clipboard".into()),
+            Selection::Primary => Err("no HTML in the PRIMARY selection".into()),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -13,18 +452,71 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_clipboard_module_exists() {
-        // Basic test to ensure the module compiles and can be tested
-        // Note: Actual clipboard testing would require system clipboard access
-        // which is not reliable in CI environments
-        assert!(true);
+    fn test_memory_provider_reads_and_writes() {
+        // The in-memory provider stands in for the system clipboard, which is
+        // not reliably available in CI environments
+        let mut clipboard = MemoryProvider::new("copied");
+        assert_eq!(clipboard.read_text(Selection::Clipboard).unwrap(), "copied");
+
+        clipboard
+            .write_text("replaced", Selection::Clipboard)
+            .unwrap();
+        assert_eq!(clipboard.contents(Selection::Clipboard), Some("replaced"));
+    }
+
+    #[test]
+    fn test_every_backend_creates_its_provider() {
+        // Every backend maps to a provider; none of them touch the clipboard until used
+        let backends = [
+            (ClipboardBackend::System, "system"),
+            (ClipboardBackend::WlClipboard, "wl-clipboard"),
+            (ClipboardBackend::Xclip, "xclip"),
+            (ClipboardBackend::Xsel, "xsel"),
+            (ClipboardBackend::Pasteboard, "pasteboard"),
+            (ClipboardBackend::Wsl, "wsl"),
+            (ClipboardBackend::Osc52, "osc52"),
+        ];
+        for (backend, name) in backends {
+            assert_eq!(create_provider(backend).name(), name);
+        }
+    }
+
+    #[test]
+    fn test_lazy_provider_is_created_on_first_use() {
+        let mut clipboard = LazyProvider::new(ClipboardBackend::Osc52);
+        assert!(clipboard.provider.get().is_none());
+        assert!(clipboard.read_html(Selection::Clipboard).is_err());
+        assert_eq!(clipboard.name(), "osc52");
     }
 
     #[test]
-    fn test_clipboard_function_signature() {
-        // Test that the function signature is correct by checking it compiles
-        // We can't easily test actual clipboard functionality without mocking
-        let _result: Result<String, Box<dyn Error>> = read_clipboard_input();
-        // This will fail in test environment but ensures correct signature
+    fn test_empty_memory_clipboard() {
+        let mut clipboard = MemoryProvider::default();
+        assert!(clipboard.read_text(Selection::Clipboard).is_err());
+    }
+
+    #[test]
+    fn test_read_flavor() {
+        let mut clipboard = MemoryProvider::with_html("text", "<b>text</b>");
+        let html = read_flavor(&mut clipboard, Selection::Clipboard, ClipboardFlavor::Html);
+        assert_eq!(html.unwrap(), "<b>text</b>");
+
+        // Backends without HTML support say so instead of returning the plain text
+        let error = Osc52Provider.read_html(Selection::Clipboard).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "the osc52 clipboard backend cannot read HTML"
+        );
+    }
+
+    #[test]
+    fn test_selections_are_independent() {
+        let mut clipboard = MemoryProvider::with_primary("selected");
+        clipboard
+            .write_text("copied", Selection::Clipboard)
+            .unwrap();
+
+        assert_eq!(clipboard.contents(Selection::Primary), Some("selected"));
+        assert_eq!(clipboard.contents(Selection::Clipboard), Some("copied"));
     }
 }
@@ -0,0 +1,241 @@
+use std::{
+    env,
+    error::Error,
+    io::Write,
+    path::Path,
+    process::{Command, Stdio},
+};
+
+use crate::clipboard_handler::{ClipboardProvider, Selection};
+
+/// Clipboard access through external command-line tools
+#[derive(Debug, Clone, Copy)]
+pub struct CommandProvider {
+    name: &'static str,
+    clipboard: Commands,
+    /// Commands for the X11/Wayland PRIMARY selection, where the tool supports it
+    primary: Option<Commands>,
+    /// Arguments added to the read command to ask for the text/html flavour
+    html_args: Option<&'static [&'static str]>,
+    /// The read command speaks CRLF and appends a trailing line ending
+    crlf_output: bool,
+}
+
+/// The commands that read and write one selection
+#[derive(Debug, Clone, Copy)]
+struct Commands {
+    read: &'static [&'static str],
+    write: &'static [&'static str],
+}
+
+/// `wl-paste`/`wl-copy` from wl-clipboard (Wayland)
+pub const WL_CLIPBOARD: CommandProvider = CommandProvider {
+    name: "wl-clipboard",
+    clipboard: Commands {
+        read: &["wl-paste", "--no-newline"],
+        write: &["wl-copy"],
+    },
+    primary: Some(Commands {
+        read: &["wl-paste", "--primary", "--no-newline"],
+        write: &["wl-copy", "--primary"],
+    }),
+    html_args: Some(&["--type", "text/html"]),
+    crlf_output: false,
+};
+
+/// `xclip` (X11)
+pub const XCLIP: CommandProvider = CommandProvider {
+    name: "xclip",
+    clipboard: Commands {
+        read: &["xclip", "-selection", "clipboard", "-out"],
+        write: &["xclip", "-selection", "clipboard", "-in"],
+    },
+    primary: Some(Commands {
+        read: &["xclip", "-selection", "primary", "-out"],
+        write: &["xclip", "-selection", "primary", "-in"],
+    }),
+    html_args: Some(&["-target", "text/html"]),
+    crlf_output: false,
+};
+
+/// `xsel` (X11)
+pub const XSEL: CommandProvider = CommandProvider {
+    name: "xsel",
+    clipboard: Commands {
+        read: &["xsel", "--clipboard", "--output"],
+        write: &["xsel", "--clipboard", "--input"],
+    },
+    primary: Some(Commands {
+        read: &["xsel", "--primary", "--output"],
+        write: &["xsel", "--primary", "--input"],
+    }),
+    html_args: None,
+    crlf_output: false,
+};
+
+/// `pbpaste`/`pbcopy` (macOS)
+pub const PASTEBOARD: CommandProvider = CommandProvider {
+    name: "pasteboard",
+    clipboard: Commands {
+        read: &["pbpaste"],
+        write: &["pbcopy"],
+    },
+    primary: None,
+    html_args: None,
+    crlf_output: false,
+};
+
+/// The Windows clipboard from inside WSL
+pub const WSL: CommandProvider = CommandProvider {
+    name: "wsl",
+    clipboard: Commands {
+        read: &[
+            "powershell.exe",
+            "-NoProfile",
+            "-NonInteractive",
+            "-Command",
+            "Get-Clipboard -Raw",
+        ],
+        write: &["clip.exe"],
+    },
+    primary: None,
+    html_args: None,
+    crlf_output: true,
+};
+
+impl CommandProvider {
+    /// Whether every program this provider needs can be found on `PATH`
+    pub fn is_available(&self) -> bool {
+        [self.clipboard.read[0], self.clipboard.write[0]]
+            .iter()
+            .all(|program| find_on_path(program))
+    }
+
+    fn commands(&self, selection: Selection) -> Result<Commands, Box<dyn Error>> {
+        match selection {
+            Selection::Clipboard => Ok(self.clipboard),
+            Selection::Primary => self
+                .primary
+                .ok_or_else(|| format!("{} has no PRIMARY selection", self.name).into()),
+        }
+    }
+
+    /// Run a read command, cleaning up the line endings of tools that add them
+    fn run_read(&self, command: &[&str], extra_args: &[&str]) -> Result<String, Box<dyn Error>> {
+        let (program, args) = command.split_first().expect("non-empty command");
+        let output = Command::new(program)
+            .args(args)
+            .args(extra_args)
+            .stdin(Stdio::null())
+            .output()
+            .map_err(|e| format!("failed to run {program}: {e}"))?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(format!("{program} failed: {}", stderr.trim()).into());
+        }
+
+        let text = String::from_utf8(output.stdout)?;
+        if self.crlf_output {
+            let text = text.replace("\r\n", "\n");
+            return Ok(text.strip_suffix('\n').unwrap_or(&text).to_string());
+        }
+        Ok(text)
+    }
+}
+
+impl ClipboardProvider for CommandProvider {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn read_text(&mut self, selection: Selection) -> Result<String, Box<dyn Error>> {
+        let commands = self.commands(selection)?;
+        self.run_read(commands.read, &[])
+    }
+
+    fn read_html(&mut self, selection: Selection) -> Result<String, Box<dyn Error>> {
+        let commands = self.commands(selection)?;
+        let html_args = self
+            .html_args
+            .ok_or_else(|| format!("the {} clipboard backend cannot read HTML", self.name))?;
+        self.run_read(commands.read, html_args)
+    }
+
+    fn write_text(&mut self, text: &str, selection: Selection) -> Result<(), Box<dyn Error>> {
+        let commands = self.commands(selection)?;
+        let (program, args) = commands.write.split_first().expect("non-empty command");
+        // The tools fork to keep serving the selection, so their output must not
+        // hold our stdout open
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| format!("failed to run {program}: {e}"))?;
+
+        child
+            .stdin
+            .take()
+            .ok_or_else(|| format!("failed to open {program} input"))?
+            .write_all(text.as_bytes())?;
+
+        if !child.wait()?.success() {
+            return Err(format!("{program} failed").into());
+        }
+        Ok(())
+    }
+}
+
+fn find_on_path(program: &str) -> bool {
+    env::var_os("PATH").is_some_and(|paths| {
+        env::split_paths(&paths).any(|dir| Path::new(&dir).join(program).is_file())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_provider_names() {
+        let providers = [WL_CLIPBOARD, XCLIP, XSEL, PASTEBOARD, WSL];
+        let names: Vec<&str> = providers.iter().map(ClipboardProvider::name).collect();
+        assert_eq!(
+            names,
+            ["wl-clipboard", "xclip", "xsel", "pasteboard", "wsl"]
+        );
+    }
+
+    #[test]
+    fn test_find_on_path() {
+        assert!(!find_on_path("quot-test-program-that-does-not-exist"));
+    }
+
+    #[test]
+    fn test_missing_program_reports_error() {
+        let mut provider = CommandProvider {
+            name: "missing",
+            clipboard: Commands {
+                read: &["quot-test-program-that-does-not-exist"],
+                write: &["quot-test-program-that-does-not-exist"],
+            },
+            primary: None,
+            html_args: None,
+            crlf_output: false,
+        };
+        assert!(!provider.is_available());
+        assert!(provider.read_text(Selection::Clipboard).is_err());
+        assert!(provider.write_text("x", Selection::Clipboard).is_err());
+    }
+
+    #[test]
+    fn test_primary_selection_support() {
+        assert!(XCLIP.commands(Selection::Primary).is_ok());
+        assert!(WL_CLIPBOARD.commands(Selection::Primary).is_ok());
+
+        // Only X11 and Wayland have a PRIMARY selection
+        let error = PASTEBOARD.commands(Selection::Primary).unwrap_err();
+        assert_eq!(error.to_string(), "pasteboard has no PRIMARY selection");
+    }
+}
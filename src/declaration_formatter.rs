@@ -0,0 +1,104 @@
+use std::error::Error;
+
+use crate::Language;
+
+/// What kind of value the declaration holds, which decides its type annotation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueKind {
+    /// A string literal, possibly split into concatenated chunks
+    Str,
+    /// An array or list of string literals
+    StrList,
+    /// The result of a language-specific decode expression
+    Decoded,
+}
+
+/// Wrap an already rendered value in a named declaration for the target language
+pub fn format_declaration(
+    name: &str,
+    value: &str,
+    kind: ValueKind,
+    language: Language,
+) -> Result<String, Box<dyn Error>> {
+    let declaration = match (language, kind) {
+        (Language::Rust, ValueKind::Str) => format!("pub const {name}: &str = {value};"),
+        (Language::Rust, ValueKind::StrList) => format!("pub const {name}: &[&str] = {value};"),
+        (Language::C | Language::Cpp, ValueKind::Str) => {
+            format!("static const char {name}[] = {value};")
+        }
+        (Language::C | Language::Cpp, ValueKind::StrList) => {
+            format!("static const char *const {name}[] = {value};")
+        }
+        (Language::Go, ValueKind::Str) => format!("const {name} = {value}"),
+        (Language::Go, ValueKind::StrList | ValueKind::Decoded) => format!("var {name} = {value}"),
+        (Language::Python, _) => format!("{name} = {value}"),
+        (Language::JavaScript, _) => format!("const {name} = {value};"),
+        (Language::TypeScript, _) => format!("export const {name} = {value};"),
+        (Language::Java, ValueKind::Str) => format!("public static final String {name} = {value};"),
+        (Language::Java, ValueKind::StrList) => {
+            format!("public static final String[] {name} = {value};")
+        }
+        (Language::Java, ValueKind::Decoded) => {
+            format!("public static final byte[] {name} = {value};")
+        }
+        (Language::Lua, _) => format!("local {name} = {value}"),
+        (Language::Rust | Language::C | Language::Cpp, ValueKind::Decoded) => {
+            return Err(format!("decoded declarations are not supported for {language}").into());
+        }
+    };
+    Ok(declaration)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_string_declarations() {
+        let rust = format_declaration("FOO", "r#\"x\"#", ValueKind::Str, Language::Rust).unwrap();
+        assert_eq!(rust, "pub const FOO: &str = r#\"x\"#;");
+
+        let c = format_declaration("FOO", "\"x\"", ValueKind::Str, Language::C).unwrap();
+        assert_eq!(c, "static const char FOO[] = \"x\";");
+
+        let python = format_declaration("FOO", "\"x\"", ValueKind::Str, Language::Python).unwrap();
+        assert_eq!(python, "FOO = \"x\"");
+
+        let ts = format_declaration("FOO", "\"x\"", ValueKind::Str, Language::TypeScript).unwrap();
+        assert_eq!(ts, "export const FOO = \"x\";");
+    }
+
+    #[test]
+    fn test_list_declarations() {
+        let rust = format_declaration("FOO", "&[]", ValueKind::StrList, Language::Rust).unwrap();
+        assert_eq!(rust, "pub const FOO: &[&str] = &[];");
+
+        let java = format_declaration("FOO", "{}", ValueKind::StrList, Language::Java).unwrap();
+        assert_eq!(java, "public static final String[] FOO = {};");
+    }
+
+    #[test]
+    fn test_decoded_declarations() {
+        let java = format_declaration(
+            "FOO",
+            "java.util.HexFormat.of().parseHex(\"00\")",
+            ValueKind::Decoded,
+            Language::Java,
+        )
+        .unwrap();
+        assert_eq!(
+            java,
+            "public static final byte[] FOO = java.util.HexFormat.of().parseHex(\"00\");"
+        );
+
+        let go = format_declaration(
+            "foo",
+            "func() []byte { ... }()",
+            ValueKind::Decoded,
+            Language::Go,
+        );
+        assert_eq!(go.unwrap(), "var foo = func() []byte { ... }()");
+
+        assert!(format_declaration("FOO", "x", ValueKind::Decoded, Language::Rust).is_err());
+    }
+}
@@ -0,0 +1,105 @@
+use std::error::Error;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+
+use crate::Language;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Base64,
+    Hex,
+}
+
+/// Encode bytes into the ASCII payload that goes inside the string literal
+pub fn encode(bytes: &[u8], encoding: Encoding) -> String {
+    match encoding {
+        Encoding::Base64 => STANDARD.encode(bytes),
+        Encoding::Hex => bytes.iter().map(|b| format!("{b:02x}")).collect(),
+    }
+}
+
+/// Wrap an already quoted literal in the target language's decode expression
+pub fn wrap_decode(
+    literal: &str,
+    encoding: Encoding,
+    language: Language,
+) -> Result<String, Box<dyn Error>> {
+    let wrapped = match (language, encoding) {
+        (Language::Python, Encoding::Base64) => format!("base64.b64decode({literal})"),
+        (Language::Python, Encoding::Hex) => format!("bytes.fromhex({literal})"),
+        (Language::JavaScript | Language::TypeScript, Encoding::Base64) => {
+            format!("atob({literal})")
+        }
+        (Language::JavaScript | Language::TypeScript, Encoding::Hex) => {
+            format!("Buffer.from({literal}, \"hex\")")
+        }
+        (Language::Java, Encoding::Base64) => {
+            format!("java.util.Base64.getDecoder().decode({literal})")
+        }
+        (Language::Java, Encoding::Hex) => format!("java.util.HexFormat.of().parseHex({literal})"),
+        (Language::Go, Encoding::Base64) => {
+            go_must_decode("base64.StdEncoding.DecodeString", literal)
+        }
+        (Language::Go, Encoding::Hex) => go_must_decode("hex.DecodeString", literal),
+        // Neither standard library ships a decoder that can be called inline
+        (Language::Rust | Language::C | Language::Cpp | Language::Lua, _) => {
+            return Err(format!("--decode is not supported for {language}").into());
+        }
+    };
+    Ok(wrapped)
+}
+
+/// Go's decoders also return an error, so a bare call can't be used as a
+/// value; wrap it in a function literal that panics on a malformed payload
+/// and is called straight away
+fn go_must_decode(decoder: &str, literal: &str) -> String {
+    format!(
+        "func() []byte {{ b, err := {decoder}({literal}); if err != nil {{ panic(err) }}; return b }}()"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_base64() {
+        assert_eq!(
+            encode(b"Hello, world!", Encoding::Base64),
+            "SGVsbG8sIHdvcmxkIQ=="
+        );
+        assert_eq!(encode(b"", Encoding::Base64), "");
+    }
+
+    #[test]
+    fn test_encode_hex() {
+        assert_eq!(encode(&[0x00, 0x0f, 0xab, 0xff], Encoding::Hex), "000fabff");
+    }
+
+    #[test]
+    fn test_wrap_decode() {
+        let wrapped = wrap_decode("\"SGk=\"", Encoding::Base64, Language::Python).unwrap();
+        assert_eq!(wrapped, "base64.b64decode(\"SGk=\")");
+
+        let wrapped = wrap_decode("'4869'", Encoding::Hex, Language::JavaScript).unwrap();
+        assert_eq!(wrapped, "Buffer.from('4869', \"hex\")");
+
+        let wrapped = wrap_decode("\"SGk=\"", Encoding::Base64, Language::JavaScript).unwrap();
+        assert_eq!(wrapped, "atob(\"SGk=\")");
+    }
+
+    #[test]
+    fn test_wrap_decode_go_is_a_single_value() {
+        let wrapped = wrap_decode("\"4869\"", Encoding::Hex, Language::Go).unwrap();
+        assert_eq!(
+            wrapped,
+            "func() []byte { b, err := hex.DecodeString(\"4869\"); if err != nil { panic(err) }; return b }()"
+        );
+    }
+
+    #[test]
+    fn test_wrap_decode_unsupported_language() {
+        let result = wrap_decode("\"SGk=\"", Encoding::Base64, Language::Rust);
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,260 @@
+use memchr::{memchr, memchr2, memchr3};
+use unicode_width::UnicodeWidthChar;
+
+use crate::QuoteFormat;
+
+/// How each byte that needs escaping is written in one literal style: a
+/// backslash followed by the byte's escape letter. Only ASCII bytes are ever
+/// escaped, so escaping never splits a UTF-8 character.
+#[derive(Debug)]
+pub struct EscapeTable {
+    /// Escape letter for each ASCII byte, or 0 if it is written as it is
+    letters: [u8; 128],
+    /// The bytes with a replacement, searched for with `memchr`
+    needles: &'static [u8],
+}
+
+/// Double-quoted literals
+pub static DOUBLE: EscapeTable = EscapeTable::new(b"\\\"\n\r\t");
+/// Single-quoted literals
+pub static SINGLE: EscapeTable = EscapeTable::new(b"\\'\n\r\t");
+/// Raw strings keep every byte as it is
+pub static RAW: EscapeTable = EscapeTable::new(b"");
+
+/// The escape table for a quote format
+pub fn escape_table(quote_format: QuoteFormat) -> &'static EscapeTable {
+    match quote_format {
+        QuoteFormat::Double => &DOUBLE,
+        QuoteFormat::Single => &SINGLE,
+        QuoteFormat::Raw => &RAW,
+    }
+}
+
+impl EscapeTable {
+    /// Build a table escaping the given bytes with a backslash, using the usual
+    /// letter escapes for control characters
+    const fn new(needles: &'static [u8]) -> Self {
+        // Two memchr3 groups are searched, so at most six needles fit
+        assert!(needles.len() <= 6);
+
+        let mut letters = [0; 128];
+        let mut i = 0;
+        while i < needles.len() {
+            let byte = needles[i];
+            letters[byte as usize] = match byte {
+                b'\\' | b'"' | b'\'' => byte,
+                b'\n' => b'n',
+                b'\r' => b'r',
+                b'\t' => b't',
+                _ => panic!("no escape sequence for this byte"),
+            };
+            i += 1;
+        }
+        EscapeTable { letters, needles }
+    }
+
+    /// The letter written after a backslash in place of `byte`, if it needs escaping
+    pub fn escape_letter(&self, byte: u8) -> Option<u8> {
+        match self.letters.get(byte as usize) {
+            Some(&letter) if letter != 0 => Some(letter),
+            _ => None,
+        }
+    }
+
+    /// Columns a character takes up once escaped: two for an escape sequence,
+    /// otherwise its display width, so CJK and emoji count double
+    pub fn escaped_len(&self, c: char) -> usize {
+        match u8::try_from(c)
+            .ok()
+            .and_then(|byte| self.escape_letter(byte))
+        {
+            Some(_) => 2,
+            // Unescaped control characters, such as a raw newline, count as one
+            None => c.width().unwrap_or(1),
+        }
+    }
+}
+
+/// Append the escaped form of `input` to `output` in a single pass, copying
+/// the runs between escaped characters in bulk
+pub fn escape_into(input: &str, table: &EscapeTable, output: &mut String) {
+    let bytes = input.as_bytes();
+    let mut scanner = Scanner::new(table);
+    let mut start = 0;
+
+    while let Some(position) = scanner.next(bytes, start) {
+        // Needles are ASCII, so `position` is always a character boundary
+        output.push_str(&input[start..position]);
+        if let Some(letter) = table.escape_letter(bytes[position]) {
+            output.push('\\');
+            output.push(char::from(letter));
+        }
+        start = position + 1;
+    }
+    output.push_str(&input[start..]);
+}
+
+/// Byte-oriented [`escape_into`] for input read in chunks
+pub fn escape_bytes_into(input: &[u8], table: &EscapeTable, output: &mut Vec<u8>) {
+    let mut scanner = Scanner::new(table);
+    let mut start = 0;
+
+    while let Some(position) = scanner.next(input, start) {
+        output.extend_from_slice(&input[start..position]);
+        if let Some(letter) = table.escape_letter(input[position]) {
+            output.extend_from_slice(&[b'\\', letter]);
+        }
+        start = position + 1;
+    }
+    output.extend_from_slice(&input[start..]);
+}
+
+/// Escape `input` into a new string
+pub fn escape(input: &str, table: &EscapeTable) -> String {
+    let mut output = String::with_capacity(input.len() + input.len() / 8);
+    escape_into(input, table, &mut output);
+    output
+}
+
+/// Bytes checked one at a time through the table before handing over to
+/// `memchr`, which only pays off for runs longer than this
+const PROBE_LEN: usize = 16;
+
+/// Finds the next needle with up to two vectorised `memchr` searches. Each
+/// group remembers its last hit, so a group is only searched again once the
+/// scan has moved past it; a rare needle is therefore scanned for once, not
+/// once per escape.
+struct Scanner<'a> {
+    table: &'a EscapeTable,
+    groups: [Group<'a>; 2],
+}
+
+struct Group<'a> {
+    needles: &'a [u8],
+    /// Result of the last search: `None` before searching, `Some(None)` once
+    /// the rest of the input is known to be free of these needles
+    next: Option<Option<usize>>,
+}
+
+impl<'a> Scanner<'a> {
+    fn new(table: &'a EscapeTable) -> Self {
+        let needles = table.needles;
+        let split = needles.len().min(3);
+        Scanner {
+            table,
+            groups: [Group::new(&needles[..split]), Group::new(&needles[split..])],
+        }
+    }
+
+    /// Position of the first needle at or after `from`
+    fn next(&mut self, haystack: &[u8], from: usize) -> Option<usize> {
+        // Escapes often come in clusters, where a table lookup beats starting a search
+        let probe_end = haystack.len().min(from + PROBE_LEN);
+        let probe = haystack[from..probe_end]
+            .iter()
+            .position(|&byte| self.table.escape_letter(byte).is_some());
+        if let Some(offset) = probe {
+            return Some(from + offset);
+        }
+
+        let [first, second] = &mut self.groups;
+        match (
+            first.next(haystack, probe_end),
+            second.next(haystack, probe_end),
+        ) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+}
+
+impl<'a> Group<'a> {
+    fn new(needles: &'a [u8]) -> Self {
+        Group {
+            needles,
+            // With nothing to look for, there is never a match
+            next: needles.is_empty().then_some(None),
+        }
+    }
+
+    fn next(&mut self, haystack: &[u8], from: usize) -> Option<usize> {
+        match self.next {
+            Some(None) => return None,
+            Some(Some(position)) if position >= from => return Some(position),
+            _ => {}
+        }
+
+        let rest = &haystack[from..];
+        let found = match *self.needles {
+            [a] => memchr(a, rest),
+            [a, b] => memchr2(a, b, rest),
+            [a, b, c] => memchr3(a, b, c, rest),
+            _ => unreachable!("groups hold one to three needles"),
+        };
+        let position = found.map(|offset| from + offset);
+        self.next = Some(position);
+        position
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape_double() {
+        let input = "Hello \"world\"\nNew line\tTab\\Backslash\rCarriage return";
+        let expected = "Hello \\\"world\\\"\\nNew line\\tTab\\\\Backslash\\rCarriage return";
+        assert_eq!(escape(input, &DOUBLE), expected);
+    }
+
+    #[test]
+    fn test_escape_single_leaves_double_quotes() {
+        assert_eq!(escape("it's \"x\"", &SINGLE), "it\\'s \"x\"");
+    }
+
+    #[test]
+    fn test_raw_table_escapes_nothing() {
+        let input = "\\ \" ' \n";
+        assert_eq!(escape(input, &RAW), input);
+    }
+
+    #[test]
+    fn test_matches_chained_replace() {
+        let inputs = [
+            "",
+            "\\",
+            "no escapes at all",
+            "\n\n\n",
+            "tab\tat\tevery\tword",
+            "\\n\\r\\t",
+            "mixed \u{1f980} \"quotes\" and \u{65e5}\u{672c}\r\n",
+        ];
+        for input in inputs {
+            let expected = input
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('\n', "\\n")
+                .replace('\r', "\\r")
+                .replace('\t', "\\t");
+            assert_eq!(escape(input, &DOUBLE), expected, "input {input:?}");
+        }
+    }
+
+    #[test]
+    fn test_escape_bytes_into() {
+        let mut output = b"prefix ".to_vec();
+        escape_bytes_into(b"a\"b\n", &DOUBLE, &mut output);
+        assert_eq!(output, b"prefix a\\\"b\\n");
+    }
+
+    #[test]
+    fn test_escaped_len() {
+        assert_eq!(DOUBLE.escaped_len('"'), 2);
+        assert_eq!(SINGLE.escaped_len('"'), 1);
+        assert_eq!(DOUBLE.escaped_len('\u{e9}'), 1);
+        assert_eq!(RAW.escaped_len('\n'), 1);
+        assert_eq!(DOUBLE.escaped_len('日'), 2);
+        assert_eq!(DOUBLE.escaped_len('\u{301}'), 0);
+    }
+}
@@ -11,7 +11,13 @@ pub fn read_file_content<P: AsRef<Path>>(file_path: P) -> Result<String, io::Err
     Ok(content)
 }
 
-#[allow(dead_code)]
+pub fn read_file_bytes<P: AsRef<Path>>(file_path: P) -> Result<Vec<u8>, io::Error> {
+    let mut file = File::open(file_path)?;
+    let mut content = Vec::new();
+    file.read_to_end(&mut content)?;
+    Ok(content)
+}
+
 pub fn read_file_lines<P: AsRef<Path>>(file_path: P) -> Result<Vec<String>, io::Error> {
     let file = File::open(file_path)?;
     let reader = BufReader::new(file);
@@ -48,6 +54,24 @@ mod tests {
         fs::remove_file(test_file).unwrap();
     }
 
+    #[test]
+    fn test_read_file_bytes() {
+        // Binary content that is not valid UTF-8 must still be readable
+        let test_content = [0x00, 0xff, 0xfe, b'\n'];
+        let test_file = "test_bytes_temp.bin";
+
+        {
+            let mut file = File::create(test_file).unwrap();
+            file.write_all(&test_content).unwrap();
+        }
+
+        let result = read_file_bytes(test_file).unwrap();
+        assert_eq!(result, test_content);
+
+        // Clean up
+        fs::remove_file(test_file).unwrap();
+    }
+
     #[test]
     fn test_file_exists() {
         assert!(!file_exists("non_existent_file.txt"));
@@ -0,0 +1,269 @@
+use std::path::Path;
+
+use crate::{Language, NameCase};
+
+/// Name used when neither `--name` nor an input file is available
+pub const DEFAULT_NAME: &str = "data";
+
+/// Conventional case for constants in the target language
+pub fn default_case(language: Language) -> NameCase {
+    match language {
+        // Lowercase identifiers stay package-private in Go
+        Language::Go => NameCase::Camel,
+        _ => NameCase::UpperSnake,
+    }
+}
+
+/// Derive a raw name from a file path, keeping the extension so that
+/// `query.sql` and `query.txt` do not collide
+pub fn name_from_path<P: AsRef<Path>>(path: P) -> String {
+    path.as_ref()
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| DEFAULT_NAME.to_string())
+}
+
+/// Turn an arbitrary string into a valid identifier in the requested case
+pub fn to_identifier(raw: &str, case: NameCase) -> String {
+    let words = split_words(raw);
+    if words.is_empty() {
+        return to_identifier(DEFAULT_NAME, case);
+    }
+
+    let identifier = match case {
+        NameCase::UpperSnake => words.join("_").to_uppercase(),
+        NameCase::Snake => words.join("_"),
+        NameCase::Camel => {
+            let mut identifier = words[0].clone();
+            words[1..]
+                .iter()
+                .for_each(|w| identifier.push_str(&capitalize(w)));
+            identifier
+        }
+        NameCase::Pascal => words.iter().map(|w| capitalize(w)).collect(),
+        NameCase::Preserve => raw
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect(),
+    };
+
+    // Identifiers cannot start with a digit in any supported language
+    if identifier.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("_{identifier}")
+    } else {
+        identifier
+    }
+}
+
+/// Make an identifier usable when it collides with a reserved word of the
+/// target language: a raw identifier in Rust, a trailing `_` elsewhere
+pub fn escape_keyword(identifier: String, language: Language) -> String {
+    if !keywords(language)
+        .split_ascii_whitespace()
+        .any(|keyword| keyword == identifier)
+    {
+        return identifier;
+    }
+    match language {
+        // These four cannot be raw identifiers
+        Language::Rust if !matches!(identifier.as_str(), "self" | "Self" | "super" | "crate") => {
+            format!("r#{identifier}")
+        }
+        Language::Rust
+        | Language::C
+        | Language::Cpp
+        | Language::Go
+        | Language::Python
+        | Language::JavaScript
+        | Language::TypeScript
+        | Language::Java
+        | Language::Lua => format!("{identifier}_"),
+    }
+}
+
+/// Words that cannot name a constant or variable, including those reserved
+/// for future use
+fn keywords(language: Language) -> &'static str {
+    match language {
+        Language::Rust => RUST_KEYWORDS,
+        Language::C => C_KEYWORDS,
+        Language::Cpp => CPP_KEYWORDS,
+        Language::Go => GO_KEYWORDS,
+        Language::Python => PYTHON_KEYWORDS,
+        Language::JavaScript | Language::TypeScript => JAVASCRIPT_KEYWORDS,
+        Language::Java => JAVA_KEYWORDS,
+        Language::Lua => LUA_KEYWORDS,
+    }
+}
+
+const RUST_KEYWORDS: &str = "\
+    Self abstract as async await become box break const continue crate do dyn else enum \
+    extern false final fn for gen if impl in let loop macro match mod move mut override priv \
+    pub ref return self static struct super trait true try type typeof unsafe unsized use \
+    virtual where while yield";
+
+const C_KEYWORDS: &str = "\
+    alignas alignof auto bool break case char const constexpr continue default do double \
+    else enum extern false float for goto if inline int long nullptr register restrict \
+    return short signed sizeof static static_assert struct switch thread_local true typedef \
+    typeof union unsigned void volatile while";
+
+const CPP_KEYWORDS: &str = "\
+    alignas alignof and and_eq asm auto bitand bitor bool break case catch char char16_t \
+    char32_t char8_t class co_await co_return co_yield compl concept const const_cast \
+    consteval constexpr constinit continue decltype default delete do double dynamic_cast \
+    else enum explicit export extern false float for friend goto if inline int long mutable \
+    namespace new noexcept not not_eq nullptr operator or or_eq private protected public \
+    register reinterpret_cast requires return short signed sizeof static static_assert \
+    static_cast struct switch template this thread_local throw true try typedef typeid \
+    typename union unsigned using virtual void volatile wchar_t while xor xor_eq";
+
+const GO_KEYWORDS: &str = "\
+    break case chan const continue default defer else fallthrough for func go goto if import \
+    interface map package range return select struct switch type var";
+
+const PYTHON_KEYWORDS: &str = "\
+    False None True and as assert async await break class continue def del elif else except \
+    finally for from global if import in is lambda nonlocal not or pass raise return try \
+    while with yield";
+
+/// Includes the words reserved in strict mode and modules, where TypeScript
+/// output usually ends up
+const JAVASCRIPT_KEYWORDS: &str = "\
+    arguments await break case catch class const continue debugger default delete do else \
+    enum eval export extends false finally for function if implements import in instanceof \
+    interface let new null package private protected public return static super switch this \
+    throw true try typeof var void while with yield";
+
+const JAVA_KEYWORDS: &str = "\
+    abstract assert boolean break byte case catch char class const continue default do \
+    double else enum extends false final finally float for goto if implements import \
+    instanceof int interface long native new null package private protected public return \
+    short static strictfp super switch synchronized this throw throws transient true try \
+    void volatile while";
+
+const LUA_KEYWORDS: &str = "\
+    and break do else elseif end false for function goto if in local nil not or repeat \
+    return then true until while";
+
+/// Split on non-alphanumeric characters and lower-to-upper case transitions,
+/// returning lowercase words
+fn split_words(raw: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut previous_lower = false;
+
+    for c in raw.chars() {
+        if !c.is_ascii_alphanumeric() {
+            if !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+            previous_lower = false;
+            continue;
+        }
+        if c.is_ascii_uppercase() && previous_lower {
+            words.push(std::mem::take(&mut current));
+        }
+        previous_lower = c.is_ascii_lowercase() || c.is_ascii_digit();
+        current.push(c.to_ascii_lowercase());
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+    words
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_case_conventions() {
+        assert_eq!(
+            to_identifier("my-query.sql", NameCase::UpperSnake),
+            "MY_QUERY_SQL"
+        );
+        assert_eq!(
+            to_identifier("my-query.sql", NameCase::Snake),
+            "my_query_sql"
+        );
+        assert_eq!(to_identifier("my-query.sql", NameCase::Camel), "myQuerySql");
+        assert_eq!(
+            to_identifier("my-query.sql", NameCase::Pascal),
+            "MyQuerySql"
+        );
+        assert_eq!(
+            to_identifier("my-query.sql", NameCase::Preserve),
+            "my_query_sql"
+        );
+    }
+
+    #[test]
+    fn test_camel_case_input_is_split() {
+        assert_eq!(
+            to_identifier("fooBarBaz", NameCase::UpperSnake),
+            "FOO_BAR_BAZ"
+        );
+    }
+
+    #[test]
+    fn test_sanitisation() {
+        assert_eq!(
+            to_identifier("404 page.html", NameCase::UpperSnake),
+            "_404_PAGE_HTML"
+        );
+        assert_eq!(to_identifier("--", NameCase::UpperSnake), "DATA");
+        assert_eq!(to_identifier("héllo wörld", NameCase::Snake), "h_llo_w_rld");
+    }
+
+    #[test]
+    fn test_reserved_words_are_escaped() {
+        let escaped = |raw: &str, case: NameCase, language: Language| {
+            escape_keyword(to_identifier(raw, case), language)
+        };
+        assert_eq!(escaped("match", NameCase::Snake, Language::Rust), "r#match");
+        assert_eq!(escaped("self", NameCase::Snake, Language::Rust), "self_");
+        assert_eq!(escaped("int", NameCase::Snake, Language::C), "int_");
+        assert_eq!(escaped("class", NameCase::Snake, Language::Cpp), "class_");
+        assert_eq!(escaped("type", NameCase::Camel, Language::Go), "type_");
+        assert_eq!(
+            escaped("class", NameCase::Snake, Language::Python),
+            "class_"
+        );
+        assert_eq!(escaped("none", NameCase::Pascal, Language::Python), "None_");
+        assert_eq!(
+            escaped("delete", NameCase::Camel, Language::JavaScript),
+            "delete_"
+        );
+        assert_eq!(
+            escaped("let", NameCase::Camel, Language::TypeScript),
+            "let_"
+        );
+        assert_eq!(
+            escaped("public", NameCase::Snake, Language::Java),
+            "public_"
+        );
+        assert_eq!(escaped("end", NameCase::Snake, Language::Lua), "end_");
+
+        // Only exact matches are reserved
+        assert_eq!(
+            escaped("match", NameCase::UpperSnake, Language::Rust),
+            "MATCH"
+        );
+        assert_eq!(escaped("types", NameCase::Camel, Language::Go), "types");
+    }
+
+    #[test]
+    fn test_name_from_path() {
+        assert_eq!(name_from_path("queries/find-user.sql"), "find-user.sql");
+        assert_eq!(name_from_path(".."), DEFAULT_NAME);
+    }
+}
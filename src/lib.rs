@@ -0,0 +1,263 @@
+//! Convert text into escaped string literals, arrays and declarations for
+//! a range of target languages.
+//!
+//! The `quot` binary is a thin command-line wrapper around this crate; build
+//! scripts can use [`build`] to embed asset files at compile time.
+//!
+//! The binary's dependencies (clap, crossterm, the clipboard libraries) sit
+//! behind the default `cli` feature; library users can turn it off with
+//! `default-features = false`.
+
+pub mod array_formatter;
+pub mod build;
+pub mod byte_array_formatter;
+pub mod declaration_formatter;
+pub mod encoding_formatter;
+pub mod escaper;
+pub mod file_handler;
+pub mod identifier;
+pub mod literal_splitter;
+pub mod module_generator;
+pub mod renderer;
+pub mod stream_escaper;
+pub mod text_normalizer;
+
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum QuoteFormat {
+    /// Use double quotes (default)
+    Double,
+    /// Use single quotes  
+    Single,
+    /// Use raw strings (Rust style)
+    Raw,
+}
+
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum OutputMode {
+    /// Escaped string literal (default)
+    Literal,
+    /// Byte array declaration, like `xxd -i`
+    Bytes,
+    /// Base64-encoded string literal
+    Base64,
+    /// Hex-encoded string literal
+    Hex,
+    /// Array or list literal with one element per input line
+    Lines,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum Language {
+    /// Rust (default)
+    Rust,
+    /// C
+    C,
+    /// C++
+    Cpp,
+    /// Go
+    Go,
+    /// Python
+    Python,
+    /// JavaScript
+    #[cfg_attr(feature = "cli", value(name = "javascript", alias = "js"))]
+    JavaScript,
+    /// TypeScript
+    #[cfg_attr(feature = "cli", value(name = "typescript", alias = "ts"))]
+    TypeScript,
+    /// Java
+    Java,
+    /// Lua
+    Lua,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum NameCase {
+    /// UPPER_SNAKE_CASE
+    UpperSnake,
+    /// snake_case
+    Snake,
+    /// camelCase
+    Camel,
+    /// PascalCase
+    Pascal,
+    /// Keep the name as given, only replacing invalid characters
+    Preserve,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum LookupStyle {
+    /// Constants only
+    None,
+    /// A `get(path)` function built on `match`
+    Match,
+    /// A `phf::Map` static (requires the `phf` crate with the `macros` feature)
+    Phf,
+}
+
+impl std::fmt::Display for Language {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // The same names `--lang` accepts
+        let name = match self {
+            Language::Rust => "rust",
+            Language::C => "c",
+            Language::Cpp => "cpp",
+            Language::Go => "go",
+            Language::Python => "python",
+            Language::JavaScript => "javascript",
+            Language::TypeScript => "typescript",
+            Language::Java => "java",
+            Language::Lua => "lua",
+        };
+        f.write_str(name)
+    }
+}
+
+/// One level of indentation for array elements and rows
+pub(crate) fn indent(language: Language) -> &'static str {
+    // gofmt indents with tabs; the other languages conventionally use four spaces
+    match language {
+        Language::Go => "\t",
+        Language::Rust
+        | Language::C
+        | Language::Cpp
+        | Language::Python
+        | Language::JavaScript
+        | Language::TypeScript
+        | Language::Java
+        | Language::Lua => "    ",
+    }
+}
+
+/// Escape the input and wrap it in the quotes of the given format
+pub fn format_literal(input_string: &str, quote_format: QuoteFormat) -> String {
+    match quote_format {
+        QuoteFormat::Double | QuoteFormat::Single => {
+            let quote = if matches!(quote_format, QuoteFormat::Double) {
+                '"'
+            } else {
+                '\''
+            };
+            let mut literal = String::with_capacity(input_string.len() + 2);
+            literal.push(quote);
+            escaper::escape_into(
+                input_string,
+                escaper::escape_table(quote_format),
+                &mut literal,
+            );
+            literal.push(quote);
+            literal
+        }
+        QuoteFormat::Raw => {
+            // For raw strings, we need to find a delimiter that doesn't conflict
+            let delimiter = find_raw_string_delimiter(input_string);
+            format!("r{delimiter}\"{input_string}\"{delimiter}")
+        }
+    }
+}
+
+/// Refuse quote formats the target language cannot read. Raw strings are
+/// written Rust style, `r#"..."#`, which no other language accepts.
+pub fn check_quote_format(
+    quote_format: QuoteFormat,
+    language: Language,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let raw_strings = match language {
+        Language::Rust => true,
+        Language::C
+        | Language::Cpp
+        | Language::Go
+        | Language::Python
+        | Language::JavaScript
+        | Language::TypeScript
+        | Language::Java
+        | Language::Lua => false,
+    };
+    if matches!(quote_format, QuoteFormat::Raw) && !raw_strings {
+        return Err(
+            format!("raw strings are Rust syntax, which {language} does not accept").into(),
+        );
+    }
+    Ok(())
+}
+
+/// Shortest run of `#` that safely delimits the content as a Rust raw string
+pub fn find_raw_string_delimiter(content: &str) -> String {
+    // Find the minimum number of # characters needed for a raw string
+    let mut max_consecutive_quotes = 0;
+    let mut current_quotes = 0;
+
+    for ch in content.chars() {
+        if ch == '"' {
+            current_quotes += 1;
+            max_consecutive_quotes = max_consecutive_quotes.max(current_quotes);
+        } else {
+            current_quotes = 0;
+        }
+    }
+
+    // Use one more # than the maximum consecutive quotes found
+    "#".repeat(max_consecutive_quotes + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_raw_string_delimiter() {
+        // Test simple case
+        let content1 = "Hello world";
+        let delimiter1 = find_raw_string_delimiter(content1);
+        assert_eq!(delimiter1, "#");
+
+        // Test with quotes
+        let content2 = "Hello \"world\"";
+        let delimiter2 = find_raw_string_delimiter(content2);
+        assert_eq!(delimiter2, "##"); // one more than the single quote
+
+        // Test with consecutive quotes
+        let content3 = "Has \"\"\" three quotes";
+        let delimiter3 = find_raw_string_delimiter(content3);
+        assert_eq!(delimiter3, "####"); // one more than three consecutive
+
+        // Test with no quotes
+        let content4 = "No quotes here";
+        let delimiter4 = find_raw_string_delimiter(content4);
+        assert_eq!(delimiter4, "#");
+    }
+
+    #[test]
+    fn test_raw_strings_are_rust_only() {
+        assert!(check_quote_format(QuoteFormat::Raw, Language::Rust).is_ok());
+        for language in [
+            Language::C,
+            Language::Cpp,
+            Language::Go,
+            Language::Python,
+            Language::JavaScript,
+            Language::TypeScript,
+            Language::Java,
+            Language::Lua,
+        ] {
+            let error = check_quote_format(QuoteFormat::Raw, language).unwrap_err();
+            assert!(error.to_string().contains(&language.to_string()));
+            assert!(check_quote_format(QuoteFormat::Double, language).is_ok());
+        }
+    }
+
+    #[cfg(feature = "cli")]
+    #[test]
+    fn test_language_display_matches_cli_names() {
+        use clap::ValueEnum;
+
+        for language in Language::value_variants() {
+            let value = language.to_possible_value().unwrap();
+            assert_eq!(language.to_string(), value.get_name());
+        }
+    }
+}
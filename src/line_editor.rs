@@ -0,0 +1,971 @@
+use std::collections::VecDeque;
+
+use crate::unicode_text;
+
+/// A line and grapheme column in the buffer
+pub type Position = (usize, usize);
+
+/// How many kills are remembered for Ctrl+Y and Alt+Y
+const KILL_RING_SIZE: usize = 16;
+
+/// How many steps back undo can go
+const UNDO_LIMIT: usize = 1000;
+
+/// How much text the undo history may hold in total. Every step keeps a copy
+/// of the whole buffer, so after a large paste it is this, not `UNDO_LIMIT`,
+/// that decides how far back undo reaches.
+const UNDO_BYTE_LIMIT: usize = 32 * 1024 * 1024;
+
+/// Killed text kept for yanking back, most recent first
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KillRing {
+    entries: VecDeque<String>,
+}
+
+impl KillRing {
+    fn push(&mut self, text: String) {
+        if self.entries.len() == KILL_RING_SIZE {
+            self.entries.pop_back();
+        }
+        self.entries.push_front(text);
+    }
+
+    /// Grow the most recent kill, so consecutive kills yank back as one
+    fn extend(&mut self, text: &str, backward: bool) {
+        match self.entries.front_mut() {
+            Some(latest) if backward => latest.insert_str(0, text),
+            Some(latest) => latest.push_str(text),
+            None => self.push(text.to_string()),
+        }
+    }
+
+    fn get(&self, index: usize) -> Option<&str> {
+        self.entries.get(index).map(String::as_str)
+    }
+
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+}
+
+/// What the previous command started, for commands that carry it on
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum Sequence {
+    #[default]
+    None,
+    /// Up or Down, aiming for this display column through shorter lines
+    Vertical { goal_width: usize },
+    /// A kill, which the next kill adds to
+    Kill,
+    /// A yank of the kill ring entry `index`, inserted from `start` to the cursor
+    Yank { start: Position, index: usize },
+    /// Typed characters, which undo takes back together
+    Typing,
+    /// Backspace or Delete, which undo takes back together
+    Deleting,
+}
+
+/// The buffer and cursor as they were before a change, for undo
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Snapshot {
+    lines: Vec<String>,
+    cursor: Position,
+}
+
+impl Snapshot {
+    fn size(&self) -> usize {
+        self.lines.iter().map(String::len).sum()
+    }
+}
+
+/// Graphemes that make up words for Alt+B, Alt+F and Alt+D
+fn is_word(grapheme: &str) -> bool {
+    grapheme.chars().next().is_some_and(char::is_alphanumeric)
+}
+
+/// Ctrl+W treats everything between whitespace as one word
+fn is_non_blank(grapheme: &str) -> bool {
+    !grapheme.chars().all(char::is_whitespace)
+}
+
+/// The text being typed in interactive mode and the cursor within it,
+/// independent of how it is drawn. Columns count grapheme clusters, so the
+/// cursor can never land inside a character.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineEditor {
+    lines: Vec<String>,
+    row: usize,
+    column: usize,
+    sequence: Sequence,
+    kill_ring: KillRing,
+    /// The buffer before each undoable step, oldest first
+    undo_stack: VecDeque<Snapshot>,
+    /// Bytes of text held in `undo_stack`
+    undo_bytes: usize,
+    /// The buffer before each undo, for redo; cleared by any new edit
+    redo_stack: Vec<Snapshot>,
+    /// Edits are joining the step [`LineEditor::begin_undo_group`] started
+    undo_group: bool,
+}
+
+impl Default for LineEditor {
+    fn default() -> Self {
+        LineEditor {
+            lines: vec![String::new()],
+            row: 0,
+            column: 0,
+            sequence: Sequence::None,
+            kill_ring: KillRing::default(),
+            undo_stack: VecDeque::new(),
+            undo_bytes: 0,
+            redo_stack: Vec::new(),
+            undo_group: false,
+        }
+    }
+}
+
+impl LineEditor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn lines(&self) -> &[String] {
+        &self.lines
+    }
+
+    /// Line and grapheme column of the cursor
+    pub fn cursor(&self) -> Position {
+        (self.row, self.column)
+    }
+
+    pub fn current_line(&self) -> &str {
+        &self.lines[self.row]
+    }
+
+    /// The buffer's lines joined with newlines
+    pub fn text(&self) -> String {
+        self.lines.join("\n")
+    }
+
+    /// Insert a character at the cursor. A combining mark joins the grapheme
+    /// before the cursor instead of adding one.
+    pub fn insert_char(&mut self, c: char) {
+        if c == '\n' {
+            self.split_line();
+            return;
+        }
+        self.record_undo(self.sequence == Sequence::Typing);
+        let line = &mut self.lines[self.row];
+        let offset = unicode_text::byte_offset(line, self.column);
+        line.insert(offset, c);
+        self.column = unicode_text::grapheme_count(&line[..offset + c.len_utf8()]);
+        self.sequence = Sequence::Typing;
+    }
+
+    /// Insert text at the cursor, starting a new line at each newline. This
+    /// is one step for undo, however many lines it spans.
+    pub fn insert_str(&mut self, text: &str) {
+        self.record_undo(false);
+        self.insert_text(text);
+        self.sequence = Sequence::None;
+    }
+
+    /// Break the current line at the cursor, moving to the start of the new line
+    pub fn split_line(&mut self) {
+        self.record_undo(false);
+        self.break_line();
+        self.sequence = Sequence::None;
+    }
+
+    /// Delete the grapheme before the cursor, joining with the previous line at column 0
+    pub fn backspace(&mut self) {
+        self.record_undo(self.sequence == Sequence::Deleting);
+        if self.column > 0 {
+            self.column -= 1;
+            self.remove_grapheme();
+        } else if self.row > 0 {
+            let line = self.lines.remove(self.row);
+            self.row -= 1;
+            self.column = unicode_text::grapheme_count(&self.lines[self.row]);
+            self.lines[self.row].push_str(&line);
+        }
+        self.sequence = Sequence::Deleting;
+    }
+
+    /// Delete the grapheme under the cursor, joining with the next line at the end
+    pub fn delete(&mut self) {
+        self.record_undo(self.sequence == Sequence::Deleting);
+        if self.column < self.line_len() {
+            self.remove_grapheme();
+        } else if self.row + 1 < self.lines.len() {
+            let next = self.lines.remove(self.row + 1);
+            self.lines[self.row].push_str(&next);
+        }
+        self.sequence = Sequence::Deleting;
+    }
+
+    /// Remove the current line, leaving the cursor at the start of the line
+    /// that takes its place
+    pub fn remove_line(&mut self) {
+        self.record_undo(false);
+        self.lines.remove(self.row);
+        if self.lines.is_empty() {
+            self.lines.push(String::new());
+        }
+        self.row = self.row.min(self.lines.len() - 1);
+        self.column = 0;
+        self.sequence = Sequence::None;
+    }
+
+    /// Move one grapheme left, wrapping to the end of the previous line
+    pub fn move_left(&mut self) {
+        if self.column > 0 {
+            self.column -= 1;
+        } else if self.row > 0 {
+            self.row -= 1;
+            self.column = self.line_len();
+        }
+        self.sequence = Sequence::None;
+    }
+
+    /// Move one grapheme right, wrapping to the start of the next line
+    pub fn move_right(&mut self) {
+        if self.column < self.line_len() {
+            self.column += 1;
+        } else if self.row + 1 < self.lines.len() {
+            self.row += 1;
+            self.column = 0;
+        }
+        self.sequence = Sequence::None;
+    }
+
+    pub fn move_up(&mut self) {
+        if self.row > 0 {
+            self.move_vertically(self.row - 1);
+        }
+    }
+
+    pub fn move_down(&mut self) {
+        if self.row + 1 < self.lines.len() {
+            self.move_vertically(self.row + 1);
+        }
+    }
+
+    pub fn move_to_line_start(&mut self) {
+        self.column = 0;
+        self.sequence = Sequence::None;
+    }
+
+    pub fn move_to_line_end(&mut self) {
+        self.column = self.line_len();
+        self.sequence = Sequence::None;
+    }
+
+    /// Move to the start of the word before the cursor
+    pub fn move_word_left(&mut self) {
+        (self.row, self.column) = self.word_start_before(is_word);
+        self.sequence = Sequence::None;
+    }
+
+    /// Move to the end of the word after the cursor
+    pub fn move_word_right(&mut self) {
+        (self.row, self.column) = self.word_end_after(is_word);
+        self.sequence = Sequence::None;
+    }
+
+    /// Kill to the end of the line, or the line break itself when already there
+    pub fn kill_to_line_end(&mut self) {
+        let end = if self.column < self.line_len() {
+            (self.row, self.line_len())
+        } else if self.row + 1 < self.lines.len() {
+            (self.row + 1, 0)
+        } else {
+            (self.row, self.column)
+        };
+        self.kill((self.row, self.column), end, false);
+    }
+
+    pub fn kill_to_line_start(&mut self) {
+        self.kill((self.row, 0), (self.row, self.column), true);
+    }
+
+    /// Kill back to the start of a word (Alt+Backspace)
+    pub fn kill_word_backward(&mut self) {
+        let start = self.word_start_before(is_word);
+        self.kill(start, (self.row, self.column), true);
+    }
+
+    /// Kill back to the previous whitespace (Ctrl+W)
+    pub fn kill_blank_word_backward(&mut self) {
+        let start = self.word_start_before(is_non_blank);
+        self.kill(start, (self.row, self.column), true);
+    }
+
+    /// Kill forward to the end of a word (Alt+D)
+    pub fn kill_word_forward(&mut self) {
+        let end = self.word_end_after(is_word);
+        self.kill((self.row, self.column), end, false);
+    }
+
+    /// Insert the most recent kill at the cursor
+    pub fn yank(&mut self) {
+        let Some(text) = self.kill_ring.get(0).map(str::to_string) else {
+            return;
+        };
+        let start = (self.row, self.column);
+        self.record_undo(false);
+        self.insert_text(&text);
+        self.sequence = Sequence::Yank { start, index: 0 };
+    }
+
+    /// Straight after a yank, replace the yanked text with the kill before it
+    pub fn yank_pop(&mut self) {
+        let Sequence::Yank { start, index } = self.sequence else {
+            return;
+        };
+        let index = (index + 1) % self.kill_ring.len();
+        let text = self.kill_ring.get(index).unwrap_or_default().to_string();
+        // Replacing the yanked text is part of the yank for undo
+        self.record_undo(true);
+        self.take_range(start, (self.row, self.column));
+        self.insert_text(&text);
+        self.sequence = Sequence::Yank { start, index };
+    }
+
+    /// Swap the graphemes either side of the cursor and move past them; at the
+    /// end of a line, swap the last two
+    pub fn transpose(&mut self) {
+        let len = self.line_len();
+        if self.column == 0 || len < 2 {
+            return;
+        }
+        self.record_undo(false);
+        let column = self.column.min(len - 1);
+        let line = &mut self.lines[self.row];
+        let mut graphemes: Vec<&str> = unicode_text::graphemes(line).collect();
+        graphemes.swap(column - 1, column);
+        *line = graphemes.concat();
+        self.column = column + 1;
+        self.sequence = Sequence::None;
+    }
+
+    /// Move the cursor to `position`, kept within the buffer
+    pub fn set_cursor(&mut self, (row, column): Position) {
+        self.row = row.min(self.lines.len() - 1);
+        self.column = column.min(self.line_len());
+        self.sequence = Sequence::None;
+    }
+
+    /// The text from `start` to `end`, which may span lines
+    pub fn text_range(&self, start: Position, end: Position) -> String {
+        let start_offset = unicode_text::byte_offset(&self.lines[start.0], start.1);
+        let end_offset = unicode_text::byte_offset(&self.lines[end.0], end.1);
+        if start.0 == end.0 {
+            return self.lines[start.0][start_offset..end_offset].to_string();
+        }
+        let mut text = self.lines[start.0][start_offset..].to_string();
+        for line in &self.lines[start.0 + 1..end.0] {
+            text.push('\n');
+            text.push_str(line);
+        }
+        text.push('\n');
+        text.push_str(&self.lines[end.0][..end_offset]);
+        text
+    }
+
+    /// Remove and return the text from `start` to `end`, leaving the cursor at `start`
+    pub fn delete_range(&mut self, start: Position, end: Position) -> String {
+        self.record_undo(false);
+        self.sequence = Sequence::None;
+        self.take_range(start, end)
+    }
+
+    /// Remove lines `first` to `last` inclusive and return them joined with
+    /// newlines, leaving the cursor at the start of the line that takes their place
+    pub fn delete_lines(&mut self, first: usize, last: usize) -> String {
+        self.record_undo(false);
+        let text = self
+            .lines
+            .drain(first..=last)
+            .collect::<Vec<_>>()
+            .join("\n");
+        if self.lines.is_empty() {
+            self.lines.push(String::new());
+        }
+        self.row = first.min(self.lines.len() - 1);
+        self.column = 0;
+        self.sequence = Sequence::None;
+        text
+    }
+
+    /// Insert `text` as whole lines before line `row`, leaving the cursor at
+    /// the start of the first of them
+    pub fn insert_lines(&mut self, row: usize, text: &str) {
+        self.record_undo(false);
+        let row = row.min(self.lines.len());
+        self.lines
+            .splice(row..row, text.split('\n').map(str::to_string));
+        self.row = row;
+        self.column = 0;
+        self.sequence = Sequence::None;
+    }
+
+    /// Make the edits from now until [`Self::end_undo_group`] a single step
+    /// for undo, such as a vi command and the text it inserts
+    pub fn begin_undo_group(&mut self) {
+        self.record_undo(false);
+        self.undo_group = true;
+    }
+
+    pub fn end_undo_group(&mut self) {
+        self.undo_group = false;
+    }
+
+    /// Take back the last step that changed the buffer, skipping any that
+    /// changed nothing. Returns whether there was one.
+    pub fn undo(&mut self) -> bool {
+        self.undo_group = false;
+        let snapshot = loop {
+            let Some(snapshot) = self.undo_stack.pop_back() else {
+                return false;
+            };
+            self.undo_bytes -= snapshot.size();
+            if snapshot.lines != self.lines {
+                break snapshot;
+            }
+        };
+        self.redo_stack.push(self.snapshot());
+        self.restore(snapshot);
+        true
+    }
+
+    /// Make the step undo took back again. Returns whether there was one.
+    pub fn redo(&mut self) -> bool {
+        let Some(snapshot) = self.redo_stack.pop() else {
+            return false;
+        };
+        self.push_undo(self.snapshot());
+        self.restore(snapshot);
+        true
+    }
+
+    fn line_len(&self) -> usize {
+        unicode_text::grapheme_count(self.current_line())
+    }
+
+    fn grapheme(&self, (row, column): Position) -> &str {
+        unicode_text::graphemes(&self.lines[row])
+            .nth(column)
+            .unwrap_or_default()
+    }
+
+    /// Remember the buffer before an edit, unless the edit carries on from the
+    /// previous one or is in a group, whose step is already remembered
+    fn record_undo(&mut self, continues: bool) {
+        if !continues && !self.undo_group {
+            self.push_undo(self.snapshot());
+        }
+        self.redo_stack.clear();
+    }
+
+    /// Forget the oldest steps once there are too many or they hold too much
+    /// text, always keeping the newest so the last change can be undone
+    fn push_undo(&mut self, snapshot: Snapshot) {
+        self.undo_bytes += snapshot.size();
+        self.undo_stack.push_back(snapshot);
+        while self.undo_stack.len() > 1
+            && (self.undo_stack.len() > UNDO_LIMIT || self.undo_bytes > UNDO_BYTE_LIMIT)
+        {
+            if let Some(oldest) = self.undo_stack.pop_front() {
+                self.undo_bytes -= oldest.size();
+            }
+        }
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            lines: self.lines.clone(),
+            cursor: self.cursor(),
+        }
+    }
+
+    fn restore(&mut self, snapshot: Snapshot) {
+        self.lines = snapshot.lines;
+        (self.row, self.column) = snapshot.cursor;
+        self.sequence = Sequence::None;
+    }
+
+    /// Insert text at the cursor, starting a new line at each newline
+    fn insert_text(&mut self, text: &str) {
+        for (i, piece) in text.split('\n').enumerate() {
+            if i > 0 {
+                self.break_line();
+            }
+            let line = &mut self.lines[self.row];
+            let offset = unicode_text::byte_offset(line, self.column);
+            line.insert_str(offset, piece);
+            self.column = unicode_text::grapheme_count(&line[..offset + piece.len()]);
+        }
+    }
+
+    fn break_line(&mut self) {
+        let line = &mut self.lines[self.row];
+        let rest = line.split_off(unicode_text::byte_offset(line, self.column));
+        self.row += 1;
+        self.lines.insert(self.row, rest);
+        self.column = 0;
+    }
+
+    /// Start of the word before the cursor: back over anything else, then
+    /// over graphemes in the word. Line breaks separate words.
+    fn word_start_before(&self, in_word: fn(&str) -> bool) -> Position {
+        let (mut row, mut column) = (self.row, self.column);
+        let mut seen_word = false;
+        loop {
+            if column == 0 {
+                if row == 0 || seen_word {
+                    break;
+                }
+                row -= 1;
+                column = unicode_text::grapheme_count(&self.lines[row]);
+            } else if in_word(self.grapheme((row, column - 1))) {
+                seen_word = true;
+                column -= 1;
+            } else if seen_word {
+                break;
+            } else {
+                column -= 1;
+            }
+        }
+        (row, column)
+    }
+
+    /// End of the word after the cursor, the mirror image of [`Self::word_start_before`]
+    fn word_end_after(&self, in_word: fn(&str) -> bool) -> Position {
+        let (mut row, mut column) = (self.row, self.column);
+        let mut seen_word = false;
+        loop {
+            if column == unicode_text::grapheme_count(&self.lines[row]) {
+                if row + 1 == self.lines.len() || seen_word {
+                    break;
+                }
+                row += 1;
+                column = 0;
+            } else if in_word(self.grapheme((row, column))) {
+                seen_word = true;
+                column += 1;
+            } else if seen_word {
+                break;
+            } else {
+                column += 1;
+            }
+        }
+        (row, column)
+    }
+
+    /// Remove the text from `start` to `end` onto the kill ring, leaving the
+    /// cursor at `start`
+    fn kill(&mut self, start: Position, end: Position, backward: bool) {
+        // Consecutive kills are one step for undo, as they are one kill ring entry
+        self.record_undo(self.sequence == Sequence::Kill);
+        let text = self.take_range(start, end);
+        if self.sequence == Sequence::Kill {
+            self.kill_ring.extend(&text, backward);
+        } else if !text.is_empty() {
+            self.kill_ring.push(text);
+        }
+        self.sequence = Sequence::Kill;
+    }
+
+    /// Remove and return the text from `start` to `end`, which may span lines,
+    /// leaving the cursor at `start`
+    fn take_range(&mut self, start: Position, end: Position) -> String {
+        let start_offset = unicode_text::byte_offset(&self.lines[start.0], start.1);
+        let end_offset = unicode_text::byte_offset(&self.lines[end.0], end.1);
+
+        let taken = if start.0 == end.0 {
+            self.lines[start.0]
+                .drain(start_offset..end_offset)
+                .collect()
+        } else {
+            let tail = self.lines[end.0].split_off(end_offset);
+            let mut taken = self.lines[start.0].split_off(start_offset);
+            for line in self.lines.drain(start.0 + 1..=end.0) {
+                taken.push('\n');
+                taken.push_str(&line);
+            }
+            self.lines[start.0].push_str(&tail);
+            taken
+        };
+        (self.row, self.column) = start;
+        taken
+    }
+
+    fn remove_grapheme(&mut self) {
+        let line = &mut self.lines[self.row];
+        let start = unicode_text::byte_offset(line, self.column);
+        let end = unicode_text::byte_offset(line, self.column + 1);
+        line.replace_range(start..end, "");
+    }
+
+    /// Move to `row`, at the grapheme closest to the column the cursor was
+    /// in when vertical movement began, without passing the end of the line
+    fn move_vertically(&mut self, row: usize) {
+        let goal = match self.sequence {
+            Sequence::Vertical { goal_width } => goal_width,
+            _ => {
+                let line = &self.lines[self.row];
+                unicode_text::display_width(&line[..unicode_text::byte_offset(line, self.column)])
+            }
+        };
+        self.sequence = Sequence::Vertical { goal_width: goal };
+
+        self.row = row;
+        let mut width = 0;
+        self.column = 0;
+        for grapheme in unicode_text::graphemes(&self.lines[row]) {
+            width += unicode_text::grapheme_width(grapheme);
+            if width > goal {
+                break;
+            }
+            self.column += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn editor(lines: &[&str], row: usize, column: usize) -> LineEditor {
+        LineEditor {
+            lines: lines.iter().map(ToString::to_string).collect(),
+            row,
+            column,
+            ..LineEditor::default()
+        }
+    }
+
+    #[test]
+    fn test_insert_after_moving_left_over_wide_characters() {
+        for text in ["café", "日本語", "a🦀b", "👍\u{1f3fd}"] {
+            let mut editor = LineEditor::new();
+            for c in text.chars() {
+                editor.insert_char(c);
+            }
+            assert_eq!(editor.text(), text);
+
+            // Left, then insert: used to panic inside a multi-byte character
+            editor.move_left();
+            editor.insert_char('X');
+            let graphemes: Vec<&str> = unicode_text::graphemes(editor.current_line()).collect();
+            assert_eq!(graphemes[editor.cursor().1 - 1], "X", "in {graphemes:?}");
+        }
+    }
+
+    #[test]
+    fn test_combining_mark_joins_previous_grapheme() {
+        let mut editor = editor(&["e"], 0, 1);
+        editor.insert_char('\u{301}');
+        assert_eq!(editor.cursor(), (0, 1));
+        assert_eq!(editor.current_line(), "e\u{301}");
+    }
+
+    #[test]
+    fn test_backspace_and_delete_whole_graphemes() {
+        let mut editor = editor(&["a👨\u{200d}👩\u{200d}👧b"], 0, 2);
+        editor.backspace();
+        assert_eq!(editor.current_line(), "ab");
+        editor.delete();
+        assert_eq!(editor.current_line(), "a");
+        assert_eq!(editor.cursor(), (0, 1));
+    }
+
+    #[test]
+    fn test_enter_splits_line() {
+        let mut editor = editor(&["hello world"], 0, 5);
+        editor.split_line();
+        assert_eq!(editor.lines(), ["hello", " world"]);
+        assert_eq!(editor.cursor(), (1, 0));
+    }
+
+    #[test]
+    fn test_backspace_at_column_zero_joins_lines() {
+        let mut editor = editor(&["one", "two"], 1, 0);
+        editor.backspace();
+        assert_eq!(editor.lines(), ["onetwo"]);
+        assert_eq!(editor.cursor(), (0, 3));
+
+        // Nothing to join with on the first line
+        let mut editor = editor.clone();
+        editor.move_to_line_start();
+        editor.backspace();
+        assert_eq!(editor.lines(), ["onetwo"]);
+    }
+
+    #[test]
+    fn test_delete_at_end_joins_next_line() {
+        let mut editor = editor(&["one", "two"], 0, 3);
+        editor.delete();
+        assert_eq!(editor.lines(), ["onetwo"]);
+        assert_eq!(editor.cursor(), (0, 3));
+    }
+
+    #[test]
+    fn test_up_and_down_keep_the_column() {
+        let mut editor = editor(&["a long line", "ab", "another line"], 0, 6);
+        editor.move_down();
+        assert_eq!(editor.cursor(), (1, 2));
+        editor.move_down();
+        assert_eq!(editor.cursor(), (2, 6));
+        editor.move_up();
+        editor.move_up();
+        assert_eq!(editor.cursor(), (0, 6));
+        // Already on the first line
+        editor.move_up();
+        assert_eq!(editor.cursor(), (0, 6));
+    }
+
+    #[test]
+    fn test_vertical_movement_by_display_width() {
+        // Column 4 on "abcd" is below the third CJK character's left edge
+        let mut editor = editor(&["abcd", "日本語"], 0, 4);
+        editor.move_down();
+        assert_eq!(editor.cursor(), (1, 2));
+        editor.move_up();
+        assert_eq!(editor.cursor(), (0, 4));
+    }
+
+    #[test]
+    fn test_left_and_right_wrap_between_lines() {
+        let mut editor = editor(&["ab", "cd"], 1, 0);
+        editor.move_left();
+        assert_eq!(editor.cursor(), (0, 2));
+        editor.move_right();
+        assert_eq!(editor.cursor(), (1, 0));
+    }
+
+    #[test]
+    fn test_insert_str_splits_lines() {
+        let mut editor = editor(&["start end"], 0, 6);
+        editor.insert_str("one\ntwo\nthree ");
+        assert_eq!(editor.lines(), ["start one", "two", "three end"]);
+        assert_eq!(editor.cursor(), (2, 6));
+    }
+
+    #[test]
+    fn test_remove_line() {
+        let mut editor = editor(&["a", ""], 1, 0);
+        editor.remove_line();
+        assert_eq!(editor.lines(), ["a"]);
+        assert_eq!(editor.cursor(), (0, 0));
+    }
+
+    #[test]
+    fn test_word_movement() {
+        let mut editor = editor(&["foo_bar  baz", "  qux"], 0, 0);
+        editor.move_word_right();
+        assert_eq!(editor.cursor(), (0, 3));
+        editor.move_word_right();
+        assert_eq!(editor.cursor(), (0, 7));
+        editor.move_word_right();
+        assert_eq!(editor.cursor(), (0, 12));
+        // Words carry on across the line break
+        editor.move_word_right();
+        assert_eq!(editor.cursor(), (1, 5));
+        editor.move_word_left();
+        assert_eq!(editor.cursor(), (1, 2));
+        editor.move_word_left();
+        assert_eq!(editor.cursor(), (0, 9));
+    }
+
+    #[test]
+    fn test_kill_and_yank() {
+        let mut editor = editor(&["echo hello world"], 0, 16);
+        editor.kill_blank_word_backward();
+        assert_eq!(editor.current_line(), "echo hello ");
+        editor.move_to_line_start();
+        editor.yank();
+        assert_eq!(editor.current_line(), "worldecho hello ");
+        assert_eq!(editor.cursor(), (0, 5));
+    }
+
+    #[test]
+    fn test_consecutive_kills_yank_as_one() {
+        let mut editor = editor(&["one two three"], 0, 13);
+        editor.kill_blank_word_backward();
+        editor.kill_blank_word_backward();
+        assert_eq!(editor.current_line(), "one ");
+        editor.yank();
+        assert_eq!(editor.current_line(), "one two three");
+
+        // Forward kills append, and the line break is killed at the end of a line
+        let mut editor = self::editor(&["ab", "cd"], 0, 1);
+        editor.kill_to_line_end();
+        editor.kill_to_line_end();
+        assert_eq!(editor.lines(), ["acd"]);
+        editor.yank();
+        assert_eq!(editor.lines(), ["ab", "cd"]);
+    }
+
+    #[test]
+    fn test_yank_pop_cycles_the_kill_ring() {
+        let mut editor = editor(&["first second"], 0, 12);
+        editor.kill_word_backward();
+        editor.move_left();
+        editor.kill_to_line_start();
+        assert_eq!(editor.lines(), [" "]);
+
+        editor.yank();
+        assert_eq!(editor.current_line(), "first ");
+        editor.yank_pop();
+        assert_eq!(editor.current_line(), "second ");
+        editor.yank_pop();
+        assert_eq!(editor.current_line(), "first ");
+
+        // Only straight after a yank
+        editor.move_left();
+        editor.yank_pop();
+        assert_eq!(editor.current_line(), "first ");
+    }
+
+    #[test]
+    fn test_kill_word_forward_and_line_start() {
+        let mut editor = editor(&["let x = 1;"], 0, 3);
+        editor.kill_word_forward();
+        assert_eq!(editor.current_line(), "let = 1;");
+        editor.kill_to_line_start();
+        assert_eq!(editor.current_line(), " = 1;");
+        assert_eq!(editor.cursor(), (0, 0));
+    }
+
+    #[test]
+    fn test_transpose() {
+        let mut editor = editor(&["teh"], 0, 2);
+        editor.transpose();
+        assert_eq!(editor.current_line(), "the");
+        assert_eq!(editor.cursor(), (0, 3));
+
+        // At the end of the line the last two swap
+        let mut editor = self::editor(&["ab日"], 0, 3);
+        editor.transpose();
+        assert_eq!(editor.current_line(), "a日b");
+
+        let mut editor = self::editor(&["ab"], 0, 0);
+        editor.transpose();
+        assert_eq!(editor.current_line(), "ab");
+    }
+
+    #[test]
+    fn test_ranges_and_whole_lines() {
+        let mut editor = editor(&["one", "two", "three"], 0, 0);
+        assert_eq!(editor.text_range((0, 1), (2, 2)), "ne\ntwo\nth");
+        assert_eq!(editor.delete_range((0, 1), (1, 1)), "ne\nt");
+        assert_eq!(editor.lines(), ["owo", "three"]);
+
+        assert_eq!(editor.delete_lines(0, 0), "owo");
+        editor.insert_lines(1, "a\nb");
+        assert_eq!(editor.lines(), ["three", "a", "b"]);
+        assert_eq!(editor.cursor(), (1, 0));
+
+        // Deleting every line leaves one empty line
+        editor.delete_lines(0, 2);
+        assert_eq!(editor.lines(), [""]);
+    }
+
+    #[test]
+    fn test_undo_takes_back_runs_of_typing_and_deleting() {
+        let mut editor = LineEditor::new();
+        assert!(!editor.undo());
+        for c in "echo hi".chars() {
+            editor.insert_char(c);
+        }
+        editor.backspace();
+        editor.backspace();
+        editor.insert_str("pasted\ntext");
+        assert_eq!(editor.lines(), ["echo pasted", "text"]);
+
+        // The paste is one step, however many lines it spans
+        assert!(editor.undo());
+        assert_eq!(editor.lines(), ["echo "]);
+        assert!(editor.undo());
+        assert_eq!(editor.lines(), ["echo hi"]);
+        assert!(editor.undo());
+        assert_eq!(editor.lines(), [""]);
+        assert!(!editor.undo());
+    }
+
+    #[test]
+    fn test_consecutive_kills_undo_as_one() {
+        let mut editor = editor(&["one two three"], 0, 13);
+        editor.kill_word_backward();
+        editor.kill_word_backward();
+        editor.move_left();
+        editor.kill_word_backward();
+        assert_eq!(editor.lines(), [" "]);
+        editor.undo();
+        assert_eq!(editor.lines(), ["one "]);
+        editor.undo();
+        assert_eq!(editor.lines(), ["one two three"]);
+        assert_eq!(editor.cursor(), (0, 13));
+    }
+
+    #[test]
+    fn test_redo() {
+        let mut editor = editor(&["abc"], 0, 3);
+        editor.insert_str("d");
+        editor.transpose();
+        assert_eq!(editor.lines(), ["abdc"]);
+        editor.undo();
+        editor.undo();
+        assert!(editor.redo());
+        assert_eq!(editor.lines(), ["abcd"]);
+        assert_eq!(editor.cursor(), (0, 4));
+        assert!(editor.redo());
+        assert_eq!(editor.lines(), ["abdc"]);
+        assert!(!editor.redo());
+
+        // A new edit after undoing starts a new history
+        editor.undo();
+        editor.delete();
+        assert!(!editor.redo());
+    }
+
+    #[test]
+    fn test_undo_group() {
+        let mut editor = editor(&["abc"], 0, 3);
+        editor.begin_undo_group();
+        editor.delete_range((0, 0), (0, 1));
+        editor.insert_str("x");
+        editor.end_undo_group();
+        // A group where nothing changed is not a step of its own
+        editor.begin_undo_group();
+        editor.end_undo_group();
+        assert_eq!(editor.lines(), ["xbc"]);
+        assert!(editor.undo());
+        assert_eq!(editor.lines(), ["abc"]);
+        assert_eq!(editor.cursor(), (0, 3));
+    }
+
+    #[test]
+    fn test_undo_history_is_capped_by_size() {
+        // A third of the limit, in lines other than the one being edited
+        let line = "x".repeat(1024);
+        let mut lines = vec![""];
+        lines.resize(UNDO_BYTE_LIMIT / 3 / line.len() + 1, &line);
+        let mut editor = editor(&lines, 0, 0);
+        for _ in 0..5 {
+            editor.insert_str("y");
+            editor.set_cursor((0, 0));
+        }
+        assert!(editor.undo_bytes <= UNDO_BYTE_LIMIT);
+        assert_eq!(
+            editor.undo_bytes,
+            editor.undo_stack.iter().map(Snapshot::size).sum::<usize>()
+        );
+        // The oldest steps were dropped, but the latest can still be undone
+        assert!(editor.undo_stack.len() < 6);
+        assert!(editor.undo());
+        assert_eq!(editor.current_line(), "yyyy");
+    }
+}
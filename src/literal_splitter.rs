@@ -0,0 +1,184 @@
+use crate::{escaper, find_raw_string_delimiter, format_literal, Language, QuoteFormat};
+
+/// Format the input as one or more literals joined with the language's
+/// concatenation syntax so that no output line exceeds `width` columns
+pub fn split_literal(
+    input: &str,
+    quote_format: QuoteFormat,
+    language: Language,
+    width: usize,
+) -> String {
+    let overhead = line_overhead(language) + quote_overhead(input, quote_format);
+    // Every chunk has to make progress, even if that means overrunning the width
+    let budget = width.saturating_sub(overhead).max(1);
+
+    let pieces = split_pieces(input, quote_format, budget);
+    if pieces.len() <= 1 {
+        return format_literal(input, quote_format);
+    }
+
+    let literals: Vec<String> = pieces
+        .iter()
+        .map(|piece| format_literal(piece, quote_format))
+        .collect();
+    join_literals(&literals, language)
+}
+
+/// Columns each chunk line spends on indentation and concatenation syntax
+fn line_overhead(language: Language) -> usize {
+    match language {
+        // `    "...",` inside `concat!(...)`
+        Language::Rust => 5,
+        // `    "..."` inside parentheses
+        Language::Python => 4,
+        // Adjacent literals need nothing extra
+        Language::C | Language::Cpp => 0,
+        // `"..." +`
+        Language::Go | Language::Java | Language::JavaScript | Language::TypeScript => 2,
+        // `"..." ..`
+        Language::Lua => 3,
+    }
+}
+
+/// Columns taken by the quotes (and raw string delimiters) around each chunk
+fn quote_overhead(input: &str, quote_format: QuoteFormat) -> usize {
+    match quote_format {
+        QuoteFormat::Double | QuoteFormat::Single => 2,
+        // Sized for the whole input, so it is an upper bound for every chunk
+        QuoteFormat::Raw => 3 + 2 * find_raw_string_delimiter(input).len(),
+    }
+}
+
+/// Columns a character takes up once escaped, so escapes are never split apart
+fn escaped_width(c: char, quote_format: QuoteFormat) -> usize {
+    escaper::escape_table(quote_format).escaped_len(c)
+}
+
+/// Split the unescaped input on character boundaries into pieces whose escaped
+/// form fits the budget, preferring to break right after a newline
+fn split_pieces(input: &str, quote_format: QuoteFormat, budget: usize) -> Vec<&str> {
+    let mut pieces = Vec::new();
+    let mut start = 0;
+    let mut used = 0;
+    // Byte offset just past the last newline in the current piece, with the width up to it
+    let mut last_newline: Option<(usize, usize)> = None;
+
+    for (i, c) in input.char_indices() {
+        let char_width = escaped_width(c, quote_format);
+
+        while used > 0 && used + char_width > budget {
+            let (cut, cut_width) = last_newline.take().unwrap_or((i, used));
+            pieces.push(&input[start..cut]);
+            start = cut;
+            used -= cut_width;
+        }
+
+        used += char_width;
+        if c == '\n' {
+            last_newline = Some((i + 1, used));
+        }
+    }
+
+    if start < input.len() || pieces.is_empty() {
+        pieces.push(&input[start..]);
+    }
+    pieces
+}
+
+fn join_literals(literals: &[String], language: Language) -> String {
+    match language {
+        Language::Rust => {
+            let body: Vec<String> = literals.iter().map(|l| format!("    {l},")).collect();
+            format!("concat!(\n{}\n)", body.join("\n"))
+        }
+        Language::Python => {
+            let body: Vec<String> = literals.iter().map(|l| format!("    {l}")).collect();
+            format!("(\n{}\n)", body.join("\n"))
+        }
+        Language::C | Language::Cpp => literals.join("\n"),
+        Language::Go | Language::Java | Language::JavaScript | Language::TypeScript => {
+            literals.join(" +\n")
+        }
+        Language::Lua => literals.join(" ..\n"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use unicode_width::UnicodeWidthChar;
+
+    #[test]
+    fn test_short_input_is_not_split() {
+        let output = split_literal("short", QuoteFormat::Double, Language::C, 80);
+        assert_eq!(output, "\"short\"");
+    }
+
+    #[test]
+    fn test_split_respects_width() {
+        let input = "abcdefghijklmnopqrstuvwxyz";
+        let output = split_literal(input, QuoteFormat::Double, Language::C, 10);
+        for line in output.lines() {
+            assert!(line.len() <= 10, "line too long: {line:?}");
+        }
+        assert_eq!(output, "\"abcdefgh\"\n\"ijklmnop\"\n\"qrstuvwx\"\n\"yz\"");
+    }
+
+    #[test]
+    fn test_prefers_breaking_after_newline() {
+        let input = "one\ntwo three";
+        let output = split_literal(input, QuoteFormat::Double, Language::C, 12);
+        assert_eq!(output, "\"one\\n\"\n\"two three\"");
+    }
+
+    #[test]
+    fn test_never_splits_escape_sequences() {
+        let input = "a\"\"\"\"";
+        let pieces = split_pieces(input, QuoteFormat::Double, 2);
+        assert_eq!(pieces, ["a", "\"", "\"", "\"", "\""]);
+    }
+
+    #[test]
+    fn test_never_splits_utf8_characters() {
+        let input = "日本語テキスト";
+        let pieces = split_pieces(input, QuoteFormat::Double, 6);
+        assert_eq!(pieces, ["日本語", "テキス", "ト"]);
+    }
+
+    #[test]
+    fn test_wide_characters_count_as_two_columns() {
+        let input = "日本語のテキストと🦀を含む行";
+        let output = split_literal(input, QuoteFormat::Double, Language::C, 12);
+        for line in output.lines() {
+            let columns: usize = line.chars().map(|c| c.width().unwrap_or(1)).sum();
+            assert!(columns <= 12, "line too wide: {line:?}");
+        }
+        assert_eq!(output.lines().next(), Some("\"日本語のテ\""));
+    }
+
+    #[test]
+    fn test_concatenation_syntax() {
+        let input = "abcdef";
+
+        let rust = split_literal(input, QuoteFormat::Double, Language::Rust, 10);
+        assert_eq!(rust, "concat!(\n    \"abc\",\n    \"def\",\n)");
+
+        let python = split_literal(input, QuoteFormat::Double, Language::Python, 10);
+        assert_eq!(python, "(\n    \"abcd\"\n    \"ef\"\n)");
+
+        let java = split_literal(input, QuoteFormat::Double, Language::Java, 7);
+        assert_eq!(java, "\"abc\" +\n\"def\"");
+
+        let lua = split_literal(input, QuoteFormat::Double, Language::Lua, 8);
+        assert_eq!(lua, "\"abc\" ..\n\"def\"");
+    }
+
+    #[test]
+    fn test_raw_chunks_get_their_own_delimiters() {
+        let output = split_literal("ab\"cd", QuoteFormat::Raw, Language::Rust, 14);
+        assert_eq!(
+            output,
+            "concat!(\n    r#\"ab\"#,\n    r##\"\"c\"##,\n    r#\"d\"#,\n)"
+        );
+    }
+}
@@ -1,26 +1,49 @@
 use std::{
     error::Error,
-    io::{self, stdin, Read},
+    fs::{self, File},
+    io::{self, stdin, BufReader, BufWriter, Read, Write},
     process,
+    time::Duration,
 };
 
 use atty::Stream;
-use clap::{Parser, ValueEnum};
+use clap::{Parser, Subcommand};
+use clipboard_handler::{
+    ClipboardBackend, ClipboardFlavor, ClipboardProvider, LazyProvider, Selection,
+};
 use crossterm::event::{self, Event};
+use line_editor::LineEditor;
+use quot::{
+    array_formatter, check_quote_format, file_handler, identifier, module_generator,
+    renderer::{self, RenderOptions},
+    stream_escaper::{self, Spool},
+    text_normalizer, Language, LookupStyle, NameCase, OutputMode, QuoteFormat,
+};
+use regex::Regex;
+use stdin_handler::{Cancelled, InputOptions, KeyOutcome, Keymap};
+use terminal_guard::TerminalGuard;
+use vi_mode::EditingMode;
 
 mod clipboard_handler;
-mod file_handler;
+mod command_clipboard;
+mod line_editor;
+mod osc52_handler;
 mod stdin_handler;
-
-#[derive(Debug, Clone, ValueEnum)]
-enum QuoteFormat {
-    /// Use double quotes (default)
-    Double,
-    /// Use single quotes  
-    Single,
-    /// Use raw strings (Rust style)
-    Raw,
-}
+mod terminal_guard;
+mod unicode_text;
+mod vi_mode;
+mod watch_handler;
+
+/// Key bindings shown under `--help`, for the keys a user can't guess
+const INTERACTIVE_KEYS_HELP: &str = "\
+Interactive mode keys:
+  Ctrl+D               Finish input
+  Ctrl+C               Cancel without printing anything
+  Ctrl+_ or Ctrl+/     Undo (u in vi normal mode)
+  Alt+/                Redo (Ctrl+R in vi normal mode; Ctrl+Shift+Z where the
+                       terminal reports it apart from Ctrl+Z)
+  Ctrl+L               Clear the screen and redraw
+The usual readline keys also work; see the README for the full list.";
 
 #[derive(Parser)]
 #[command(name = "quot")]
@@ -28,16 +51,118 @@ enum QuoteFormat {
     about = "A fast and flexible command-line tool that converts text input into escaped string literals"
 )]
 #[command(long_about = None)]
+#[command(after_help = INTERACTIVE_KEYS_HELP)]
 #[command(version = env!("CARGO_PKG_VERSION"))]
+#[command(args_conflicts_with_subcommands = true)]
+#[command(group(
+    clap::ArgGroup::new("clipboard_sink")
+        .args(["to_clipboard", "to_primary"])
+        .multiple(true)
+))]
+#[command(group(clap::ArgGroup::new("clipboard_source").args(["clipboard", "primary"])))]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Quote format to use
     #[arg(long, short = 'm', value_enum, default_value_t = QuoteFormat::Double)]
     format: QuoteFormat,
 
+    /// Output mode
+    #[arg(long, short = 'o', value_enum, default_value_t = OutputMode::Literal)]
+    output: OutputMode,
+
+    /// Target language for language-specific output
+    #[arg(long, short = 'l', value_enum, default_value_t = Language::Rust)]
+    lang: Language,
+
+    /// Number of bytes per line in byte array output
+    #[arg(long, default_value_t = 12)]
+    bytes_per_line: usize,
+
+    /// Split long literals into concatenated chunks no wider than N columns
+    #[arg(long, short = 'w', value_name = "N")]
+    width: Option<usize>,
+
+    /// Keep each line's trailing newline in line array output
+    #[arg(long)]
+    keep_newlines: bool,
+
+    /// Drop empty and whitespace-only lines from line array output
+    #[arg(long)]
+    skip_blank_lines: bool,
+
+    /// Emit a full declaration, named after the input file unless --name is given
+    #[arg(long, short = 'd')]
+    declare: bool,
+
+    /// Name of the declared constant (implies --declare)
+    #[arg(long, short = 'n')]
+    name: Option<String>,
+
+    /// Case convention for declared names [default: the language's convention]
+    #[arg(long, value_enum)]
+    name_case: Option<NameCase>,
+
+    /// Wrap base64/hex output in the target language's decode expression
+    #[arg(long)]
+    decode: bool,
+
     /// Read text from system clipboard
     #[arg(long, short = 'c')]
     clipboard: bool,
 
+    /// Read text from the X11/Wayland PRIMARY selection (the current mouse selection)
+    #[arg(long, short = 'p', conflicts_with = "clipboard")]
+    primary: bool,
+
+    /// Which format of the copied content to read
+    #[arg(
+        long,
+        value_enum,
+        value_name = "MIME",
+        default_value_t = ClipboardFlavor::Plain,
+        requires = "clipboard_source"
+    )]
+    clipboard_type: ClipboardFlavor,
+
+    /// Convert HTML input to the plain text it displays as
+    #[arg(long)]
+    strip_html: bool,
+
+    /// Replace non-breaking spaces and smart quotes with plain ASCII
+    #[arg(long)]
+    normalize: bool,
+
+    /// Copy the result to the system clipboard instead of printing it
+    #[arg(long, short = 'C')]
+    to_clipboard: bool,
+
+    /// Put the result in the PRIMARY selection instead of printing it
+    #[arg(long, short = 'P')]
+    to_primary: bool,
+
+    /// Also print the result when copying it to the clipboard
+    #[arg(long, requires = "clipboard_sink")]
+    echo: bool,
+
+    /// How to access the clipboard
+    #[arg(long, value_enum, default_value_t = ClipboardBackend::Auto)]
+    clipboard_backend: ClipboardBackend,
+
+    /// In interactive mode, finish at the first blank line instead of on Ctrl+D
+    #[arg(long)]
+    submit_on_blank: bool,
+
+    /// In interactive mode, convert CRLF and CR line endings in pasted text to LF
+    #[arg(long)]
+    normalize_line_endings: bool,
+
+    /// Key bindings for interactive mode [default: $QUOT_EDITING_MODE, else the
+    /// editing mode set in $INPUTRC or ~/.inputrc, else emacs]
+    #[arg(long, value_enum)]
+    editing_mode: Option<EditingMode>,
+
     /// File to read from
     #[arg(long, short = 'f')]
     file: Option<String>,
@@ -46,6 +171,140 @@ struct Args {
     file_path: Option<String>,
 }
 
+#[derive(Subcommand)]
+enum Command {
+    /// Generate a Rust module with one constant per file
+    Module(ModuleArgs),
+
+    /// Replace every new clipboard copy with its escaped literal
+    Watch(WatchArgs),
+
+    /// Serve clipboard contents read from stdin (used internally by --to-clipboard)
+    #[command(name = clipboard_handler::SERVE_CLIPBOARD_COMMAND, hide = true)]
+    ServeClipboard {
+        /// Serve the PRIMARY selection instead of the clipboard
+        #[arg(long)]
+        primary: bool,
+    },
+}
+
+#[derive(clap::Args)]
+struct ModuleArgs {
+    /// Directories (walked recursively), files or glob patterns to embed; each
+    /// file is keyed by its path relative to the directory the input names
+    #[arg(required = true)]
+    paths: Vec<String>,
+
+    /// File to write the module to (default: stdout)
+    #[arg(long, value_name = "FILE")]
+    out: Option<String>,
+
+    /// Lookup function keyed by relative path
+    #[arg(long, value_enum, default_value_t = LookupStyle::None)]
+    lookup: LookupStyle,
+}
+
+#[derive(clap::Args)]
+struct WatchArgs {
+    /// Quote format to use
+    #[arg(long, short = 'm', value_enum, default_value_t = QuoteFormat::Double)]
+    format: QuoteFormat,
+
+    /// Output mode
+    #[arg(long, short = 'o', value_enum, default_value_t = OutputMode::Literal)]
+    output: OutputMode,
+
+    /// Target language for language-specific output
+    #[arg(long, short = 'l', value_enum, default_value_t = Language::Rust)]
+    lang: Language,
+
+    /// Split long literals into concatenated chunks no wider than N columns
+    #[arg(long, short = 'w', value_name = "N")]
+    width: Option<usize>,
+
+    /// Only convert copies matching this regular expression
+    #[arg(long, value_name = "REGEX")]
+    pattern: Option<Regex>,
+
+    /// How often to check the clipboard, in milliseconds
+    #[arg(long, value_name = "MS", default_value_t = 250)]
+    interval: u64,
+
+    /// How to access the clipboard
+    #[arg(long, value_enum, default_value_t = ClipboardBackend::Auto)]
+    clipboard_backend: ClipboardBackend,
+}
+
+enum InputSource {
+    Clipboard(Selection, ClipboardFlavor),
+    File(String),
+    Piped,
+    Keyboard(InputOptions),
+}
+
+impl InputSource {
+    /// Determine input source: clipboard or PRIMARY selection, file (explicit or positional), or stdin
+    fn from_args(args: &Args) -> Self {
+        if args.clipboard {
+            InputSource::Clipboard(Selection::Clipboard, args.clipboard_type)
+        } else if args.primary {
+            InputSource::Clipboard(Selection::Primary, args.clipboard_type)
+        } else if let Some(file_path) = args.file.clone().or(args.file_path.clone()) {
+            InputSource::File(file_path)
+        } else if has_piped_input() {
+            InputSource::Piped
+        } else {
+            InputSource::Keyboard(InputOptions {
+                submit_on_blank_line: args.submit_on_blank,
+                normalize_line_endings: args.normalize_line_endings,
+                editing_mode: args
+                    .editing_mode
+                    .unwrap_or_else(EditingMode::from_environment),
+            })
+        }
+    }
+}
+
+fn read_text_input(
+    source: &InputSource,
+    clipboard: &mut dyn ClipboardProvider,
+) -> Result<String, Box<dyn Error>> {
+    match source {
+        InputSource::Clipboard(selection, flavor) => {
+            clipboard_handler::read_flavor(clipboard, *selection, *flavor)
+        }
+        InputSource::File(file_path) => read_file_input(file_path),
+        InputSource::Piped => Ok(read_piped_input()?),
+        InputSource::Keyboard(options) => read_keyboard_input(*options),
+    }
+}
+
+fn read_bytes_input(
+    source: &InputSource,
+    clipboard: &mut dyn ClipboardProvider,
+) -> Result<Vec<u8>, Box<dyn Error>> {
+    match source {
+        InputSource::File(file_path) => read_file_bytes_input(file_path),
+        InputSource::Piped => Ok(read_piped_bytes()?),
+        // Clipboard and keyboard input are text only
+        _ => Ok(read_text_input(source, clipboard)?.into_bytes()),
+    }
+}
+
+fn read_lines_input(
+    source: &InputSource,
+    clipboard: &mut dyn ClipboardProvider,
+    keep_newlines: bool,
+) -> Result<Vec<String>, Box<dyn Error>> {
+    match source {
+        InputSource::File(file_path) if !keep_newlines => read_file_lines_input(file_path),
+        _ => {
+            let input_string = read_text_input(source, clipboard)?;
+            Ok(array_formatter::split_lines(&input_string, keep_newlines))
+        }
+    }
+}
+
 fn has_piped_input() -> bool {
     !atty::is(Stream::Stdin)
 }
@@ -56,12 +315,21 @@ fn read_piped_input() -> Result<String, io::Error> {
     Ok(buffer)
 }
 
-fn read_file_input(file_path: &str) -> Result<String, Box<dyn Error>> {
-    // Check if file exists
+fn read_piped_bytes() -> Result<Vec<u8>, io::Error> {
+    let mut buffer = Vec::new();
+    stdin().read_to_end(&mut buffer)?;
+    Ok(buffer)
+}
+
+fn ensure_file_exists(file_path: &str) {
     if !file_handler::file_exists(file_path) {
         eprintln!("Error: File '{file_path}' not found or is not a regular file.");
         process::exit(1);
     }
+}
+
+fn read_file_input(file_path: &str) -> Result<String, Box<dyn Error>> {
+    ensure_file_exists(file_path);
 
     // Read file content
     file_handler::read_file_content(file_path).map_err(|e| {
@@ -70,112 +338,318 @@ fn read_file_input(file_path: &str) -> Result<String, Box<dyn Error>> {
     })
 }
 
-fn read_keyboard_input() -> Result<String, io::Error> {
-    let mut builder = Vec::<String>::new();
-    let mut current_line = String::new();
-    let mut cursor_pos = 0; // Track cursor position within current line
-
-    // Enable raw mode for better input control
-    stdin_handler::enable_raw_mode()?;
-
-    // Show initial prompt with line number 1
-    stdin_handler::print_prompt_with_line_number(1)?;
-
-    loop {
-        if let Ok(event) = event::read() {
-            match event {
-                Event::Key(key_event) => {
-                    if stdin_handler::handle_key_event(
-                        key_event,
-                        &mut current_line,
-                        &mut builder,
-                        &mut cursor_pos,
-                    )? {
-                        break; // Exit signal received
+fn read_file_bytes_input(file_path: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+    ensure_file_exists(file_path);
+
+    file_handler::read_file_bytes(file_path).map_err(|e| {
+        eprintln!("Error reading file '{file_path}': {e}");
+        process::exit(1);
+    })
+}
+
+fn read_file_lines_input(file_path: &str) -> Result<Vec<String>, Box<dyn Error>> {
+    ensure_file_exists(file_path);
+
+    file_handler::read_file_lines(file_path).map_err(|e| {
+        eprintln!("Error reading file '{file_path}': {e}");
+        process::exit(1);
+    })
+}
+
+fn read_keyboard_input(options: InputOptions) -> Result<String, Box<dyn Error>> {
+    let mut editor = LineEditor::new();
+    let mut keymap = Keymap::new(options.editing_mode);
+    let mut screen = stdin_handler::Screen::new();
+
+    // Raw mode for better input control, undone however this function exits
+    let mut terminal = TerminalGuard::enter_raw_mode()?;
+    // Lets Ctrl+Enter be told apart from Enter where the terminal supports it
+    terminal.enable_keyboard_enhancement()?;
+    terminal.enable_bracketed_paste()?;
+
+    if !options.submit_on_blank_line {
+        screen.print_hint("Press Ctrl+D to finish")?;
+    }
+    // Show the prompt for line 1
+    screen.set_prompt_mark(keymap.prompt_mark());
+    screen.render(&editor)?;
+
+    let outcome = loop {
+        let Ok(event) = event::read() else {
+            continue;
+        };
+        match event {
+            Event::Key(key_event) => {
+                match stdin_handler::handle_key_event(
+                    key_event,
+                    &mut editor,
+                    &mut keymap,
+                    &mut screen,
+                    options,
+                )? {
+                    outcome @ (KeyOutcome::Submit | KeyOutcome::Abort) => break outcome,
+                    KeyOutcome::Suspend => {
+                        // Hand the terminal back to the shell while stopped
+                        screen.finish()?;
+                        terminal.suspend()?;
+
+                        // Resumed: draw the buffer afresh
+                        screen = stdin_handler::Screen::new();
+                        screen.set_prompt_mark(keymap.prompt_mark());
+                        screen.render(&editor)?;
                     }
+                    KeyOutcome::Edited | KeyOutcome::Ignored | KeyOutcome::ClearScreen => {}
                 }
-                Event::Paste(text) => {
-                    stdin_handler::handle_paste_event(
-                        text,
-                        &mut current_line,
-                        &mut builder,
-                        &mut cursor_pos,
-                    )?;
-                }
-                _ => {}
             }
+            Event::Paste(text) => {
+                stdin_handler::handle_paste_event(&text, &mut editor, &mut screen, options)?;
+            }
+            _ => {}
         }
+    };
+    screen.finish()?;
+    drop(terminal);
+
+    if outcome == KeyOutcome::Abort {
+        return Err(Cancelled.into());
+    }
+    Ok(editor.text())
+}
+
+fn exit_with_error(error: &(dyn Error + 'static)) -> ! {
+    // A cancelled prompt is not an error worth reporting, only an exit status
+    if error.is::<Cancelled>() {
+        process::exit(stdin_handler::CANCELLED_EXIT_CODE);
     }
+    eprintln!("Error: {error}");
+    process::exit(1);
+}
 
-    // Disable raw mode
-    stdin_handler::disable_raw_mode()?;
+impl Args {
+    fn render_options(&self) -> RenderOptions {
+        RenderOptions {
+            format: self.format,
+            output: self.output,
+            lang: self.lang,
+            width: self.width,
+            bytes_per_line: self.bytes_per_line,
+            keep_newlines: self.keep_newlines,
+            skip_blank_lines: self.skip_blank_lines,
+            decode: self.decode,
+            name_case: self.name_case,
+        }
+    }
 
-    Ok(builder.join("\n"))
+    /// A bare literal printed to stdout can be escaped as the input streams
+    /// in, without holding the whole input in memory
+    fn can_stream(&self) -> bool {
+        matches!(self.output, OutputMode::Literal)
+            && self.width.is_none()
+            && !self.declare
+            && self.name.is_none()
+            && !self.strip_html
+            && !self.normalize
+            && !self.to_clipboard
+            && !self.to_primary
+    }
 }
 
-fn print_result(input_string: String, quote_format: QuoteFormat) {
-    let escaped = match quote_format {
-        QuoteFormat::Double => {
-            let escaped = input_string
-                .replace('\\', "\\\\")
-                .replace('"', "\\\"")
-                .replace('\n', "\\n")
-                .replace('\r', "\\r")
-                .replace('\t', "\\t");
-            format!("\"{escaped}\"")
+/// Raw name for declarations: --name, else the input file name, else a default.
+/// Byte arrays are always declarations, so they are always named.
+fn declaration_name(args: &Args, source: &InputSource) -> Option<String> {
+    let declared = args.declare || args.name.is_some() || matches!(args.output, OutputMode::Bytes);
+    if !declared {
+        return None;
+    }
+
+    let raw_name = match (&args.name, source) {
+        (Some(name), _) => name.clone(),
+        (None, InputSource::File(file_path)) => identifier::name_from_path(file_path),
+        _ => identifier::DEFAULT_NAME.to_string(),
+    };
+    Some(raw_name)
+}
+
+fn render_output(
+    args: &Args,
+    source: &InputSource,
+    clipboard: &mut dyn ClipboardProvider,
+) -> Result<String, Box<dyn Error>> {
+    let options = args.render_options();
+    let name = declaration_name(args, source);
+
+    if args.strip_html || args.normalize {
+        let mut input_string = read_text_input(source, clipboard)?;
+        if args.strip_html {
+            input_string = text_normalizer::strip_html(&input_string);
         }
-        QuoteFormat::Single => {
-            let escaped = input_string
-                .replace('\\', "\\\\")
-                .replace('\'', "\\'")
-                .replace('\n', "\\n")
-                .replace('\r', "\\r")
-                .replace('\t', "\\t");
-            format!("'{escaped}'")
+        if args.normalize {
+            input_string = text_normalizer::normalize_typography(&input_string);
         }
-        QuoteFormat::Raw => {
-            // For raw strings, we need to find a delimiter that doesn't conflict
-            let delimiter = find_raw_string_delimiter(&input_string);
-            format!("r{delimiter}\"{input_string}\"{delimiter}")
+        return renderer::render(input_string.as_bytes(), name.as_deref(), &options);
+    }
+
+    match args.output {
+        OutputMode::Literal => {
+            let input_string = read_text_input(source, clipboard)?;
+            renderer::render_text(&input_string, name.as_deref(), &options)
         }
-    };
+        OutputMode::Lines => {
+            let lines = read_lines_input(source, clipboard, args.keep_newlines)?;
+            renderer::render_lines(lines, name.as_deref(), &options)
+        }
+        OutputMode::Bytes | OutputMode::Base64 | OutputMode::Hex => {
+            let input_bytes = read_bytes_input(source, clipboard)?;
+            renderer::render_bytes(&input_bytes, name.as_deref(), &options)
+        }
+    }
+}
 
-    println!("{escaped}");
+/// Escape a file or piped input straight to stdout
+fn stream_output(source: &InputSource, quote_format: QuoteFormat) -> Result<(), Box<dyn Error>> {
+    let mut stdout = BufWriter::new(io::stdout().lock());
+
+    match source {
+        InputSource::File(file_path) => {
+            ensure_file_exists(file_path);
+
+            let result = File::open(file_path).and_then(|file| {
+                let mut reader = BufReader::new(file);
+                stream_escaper::stream_literal(&mut reader, &mut stdout, quote_format)
+            });
+            result.unwrap_or_else(|e| {
+                eprintln!("Error reading file '{file_path}': {e}");
+                process::exit(1);
+            });
+        }
+        InputSource::Piped => {
+            let mut stdin = stdin().lock();
+            match quote_format {
+                // Pipes can't be rewound, so raw mode reads them twice from a temporary copy
+                QuoteFormat::Raw => {
+                    let mut spool = Spool::new(&mut stdin)?;
+                    stream_escaper::stream_literal(spool.reader(), &mut stdout, quote_format)?;
+                }
+                QuoteFormat::Double | QuoteFormat::Single => {
+                    stream_escaper::stream_escaped_literal(&mut stdin, &mut stdout, quote_format)?;
+                }
+            }
+        }
+        InputSource::Clipboard(..) | InputSource::Keyboard(_) => {
+            unreachable!("only files and pipes are streamed")
+        }
+    }
+
+    writeln!(stdout)?;
+    stdout.flush()?;
+    Ok(())
 }
 
-fn find_raw_string_delimiter(content: &str) -> String {
-    // Find the minimum number of # characters needed for a raw string
-    let mut max_consecutive_quotes = 0;
-    let mut current_quotes = 0;
+/// Send the result to the clipboard, the PRIMARY selection and/or stdout
+fn write_output(
+    args: &Args,
+    output: &str,
+    clipboard: &mut dyn ClipboardProvider,
+    stdout: &mut dyn Write,
+) -> Result<(), Box<dyn Error>> {
+    if args.to_clipboard {
+        clipboard.write_text(output, Selection::Clipboard)?;
+    }
+    if args.to_primary {
+        clipboard.write_text(output, Selection::Primary)?;
+    }
+    if args.echo || !(args.to_clipboard || args.to_primary) {
+        writeln!(stdout, "{output}")?;
+    }
+    Ok(())
+}
 
-    for ch in content.chars() {
-        if ch == '"' {
-            current_quotes += 1;
-            max_consecutive_quotes = max_consecutive_quotes.max(current_quotes);
-        } else {
-            current_quotes = 0;
-        }
+/// Byte arrays hold no string literals, so only the other modes care about the format
+fn ensure_quote_format(format: QuoteFormat, output: OutputMode, lang: Language) {
+    if matches!(output, OutputMode::Bytes) {
+        return;
+    }
+    if let Err(e) = check_quote_format(format, lang) {
+        eprintln!("Error: {e}; use --format double or --format single.");
+        process::exit(1);
     }
+}
 
-    // Use one more # than the maximum consecutive quotes found
-    "#".repeat(max_consecutive_quotes + 1)
+fn run_module_command(module_args: &ModuleArgs) -> Result<(), Box<dyn Error>> {
+    let entries = module_generator::collect_entries(&module_args.paths)?;
+    let module = module_generator::generate_module(&entries, module_args.lookup)?;
+
+    match &module_args.out {
+        Some(out_path) => fs::write(out_path, module)?,
+        None => print!("{module}"),
+    }
+    Ok(())
+}
+
+fn run_watch_command(watch_args: &WatchArgs) -> Result<(), Box<dyn Error>> {
+    let options = RenderOptions {
+        format: watch_args.format,
+        output: watch_args.output,
+        lang: watch_args.lang,
+        width: watch_args.width,
+        ..RenderOptions::default()
+    };
+    let convert = |text: &str| renderer::render(text.as_bytes(), None, &options);
+
+    let mut clipboard = clipboard_handler::create_provider(watch_args.clipboard_backend);
+    watch_handler::run(
+        clipboard.as_mut(),
+        &convert,
+        watch_args.pattern.clone(),
+        Duration::from_millis(watch_args.interval),
+    )
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
     let args = Args::parse();
 
-    // Determine input source: file (explicit or positional), clipboard, or stdin
-    let input_string = if args.clipboard {
-        clipboard_handler::read_clipboard_input()?
-    } else if let Some(file_path) = args.file.or(args.file_path) {
-        read_file_input(&file_path)?
-    } else if has_piped_input() {
-        read_piped_input()?
-    } else {
-        read_keyboard_input()?
-    };
+    match &args.command {
+        Some(Command::Module(module_args)) => {
+            run_module_command(module_args).unwrap_or_else(|e| exit_with_error(e.as_ref()));
+            return Ok(());
+        }
+        Some(Command::Watch(watch_args)) => {
+            ensure_quote_format(watch_args.format, watch_args.output, watch_args.lang);
+            run_watch_command(watch_args).unwrap_or_else(|e| exit_with_error(e.as_ref()));
+            return Ok(());
+        }
+        Some(Command::ServeClipboard { primary }) => {
+            let selection = if *primary {
+                Selection::Primary
+            } else {
+                Selection::Clipboard
+            };
+            return clipboard_handler::serve_clipboard(read_piped_input()?, selection);
+        }
+        None => {}
+    }
 
-    print_result(input_string, args.format);
+    let source = InputSource::from_args(&args);
+
+    if args.decode && !matches!(args.output, OutputMode::Base64 | OutputMode::Hex) {
+        eprintln!("Error: --decode requires --output base64 or --output hex.");
+        process::exit(1);
+    }
+
+    ensure_quote_format(args.format, args.output, args.lang);
+
+    if args.can_stream() && matches!(source, InputSource::File(_) | InputSource::Piped) {
+        stream_output(&source, args.format).unwrap_or_else(|e| exit_with_error(e.as_ref()));
+        return Ok(());
+    }
+
+    let mut clipboard = LazyProvider::new(args.clipboard_backend);
+
+    let output = render_output(&args, &source, &mut clipboard)
+        .unwrap_or_else(|e| exit_with_error(e.as_ref()));
+
+    write_output(&args, &output, &mut clipboard, &mut io::stdout())
+        .unwrap_or_else(|e| exit_with_error(e.as_ref()));
 
     Ok(())
 }
@@ -183,8 +657,7 @@ fn main() -> Result<(), Box<dyn Error>> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::fs;
-    use std::io::Write;
+    use clipboard_handler::MemoryProvider;
 
     #[test]
     fn test_print_result() {
@@ -259,6 +732,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::uninlined_format_args)]
     fn test_escaping_edge_cases() {
         // Test various edge cases for string escaping
         let test_cases = vec![
@@ -302,12 +776,56 @@ mod tests {
         assert!(matches!(raw, QuoteFormat::Raw));
     }
 
+    #[test]
+    fn test_output_mode_parsing() {
+        use clap::ValueEnum;
+
+        let bytes = OutputMode::from_str("bytes", true).unwrap();
+        assert!(matches!(bytes, OutputMode::Bytes));
+
+        let hex = OutputMode::from_str("hex", true).unwrap();
+        assert!(matches!(hex, OutputMode::Hex));
+
+        let lines = OutputMode::from_str("lines", true).unwrap();
+        assert!(matches!(lines, OutputMode::Lines));
+
+        let go = Language::from_str("go", true).unwrap();
+        assert_eq!(go, Language::Go);
+
+        // The short alias resolves to the same language
+        let js = Language::from_str("js", true).unwrap();
+        assert_eq!(js, Language::JavaScript);
+        assert_eq!(js.to_string(), "javascript");
+    }
+
     #[test]
     fn test_cli_structure() {
         // Test that Args structure has the expected fields
         let args = Args {
+            command: None,
             format: QuoteFormat::Double,
+            output: OutputMode::Literal,
+            lang: Language::Rust,
+            bytes_per_line: 12,
+            width: None,
+            keep_newlines: false,
+            skip_blank_lines: false,
+            declare: false,
+            name: None,
+            name_case: None,
+            decode: false,
             clipboard: false,
+            primary: false,
+            clipboard_type: ClipboardFlavor::Plain,
+            strip_html: false,
+            normalize: false,
+            to_clipboard: false,
+            to_primary: false,
+            echo: false,
+            clipboard_backend: ClipboardBackend::Auto,
+            submit_on_blank: false,
+            normalize_line_endings: false,
+            editing_mode: None,
             file: None,
             file_path: Some("test.txt".to_string()),
         };
@@ -317,6 +835,157 @@ mod tests {
         assert_eq!(args.file_path, Some("test.txt".to_string()));
     }
 
+    #[test]
+    fn test_help_lists_undo_and_redo_keys() {
+        let help = <Args as clap::CommandFactory>::command()
+            .render_help()
+            .to_string();
+        assert!(help.contains("Ctrl+_ or Ctrl+/     Undo"));
+        assert!(help.contains("Alt+/                Redo"));
+    }
+
+    #[test]
+    fn test_clipboard_output_flags() {
+        let args = Args::try_parse_from(["quot", "--to-clipboard", "--echo"]).unwrap();
+        assert!(args.to_clipboard);
+        assert!(args.echo);
+
+        // Echoing only makes sense together with a clipboard sink
+        assert!(Args::try_parse_from(["quot", "--echo"]).is_err());
+        assert!(Args::try_parse_from(["quot", "--to-primary", "--echo"]).is_ok());
+
+        // Only one selection can be the input
+        assert!(Args::try_parse_from(["quot", "--clipboard", "--primary"]).is_err());
+    }
+
+    /// Run the CLI's input → render → output flow against a fake clipboard
+    fn run_with_clipboard(argv: &[&str], clipboard: &mut MemoryProvider) -> String {
+        let args = Args::try_parse_from(argv).unwrap();
+        let source = InputSource::from_args(&args);
+        let output = render_output(&args, &source, clipboard).unwrap();
+
+        let mut stdout = Vec::new();
+        write_output(&args, &output, clipboard, &mut stdout).unwrap();
+        String::from_utf8(stdout).unwrap()
+    }
+
+    #[test]
+    fn test_clipboard_round_trip() {
+        let mut clipboard = MemoryProvider::new("it's");
+        let stdout = run_with_clipboard(&["quot", "-c", "-C", "-m", "single"], &mut clipboard);
+
+        assert_eq!(clipboard.contents(Selection::Clipboard), Some("'it\\'s'"));
+        assert_eq!(stdout, "");
+    }
+
+    #[test]
+    fn test_html_clipboard_input() {
+        // Browsers render NBSP and curly quotes into the plain-text flavour
+        let text = "it\u{2019}s\u{a0}done";
+        let html = "<p>it&rsquo;s&nbsp;<b>done</b></p>";
+
+        let mut clipboard = MemoryProvider::with_html(text, html);
+        let stdout = run_with_clipboard(&["quot", "-c"], &mut clipboard);
+        assert_eq!(stdout, "\"it\u{2019}s\u{a0}done\"\n");
+
+        let stdout = run_with_clipboard(&["quot", "-c", "--normalize"], &mut clipboard);
+        assert_eq!(stdout, "\"it's done\"\n");
+
+        let stdout = run_with_clipboard(
+            &[
+                "quot",
+                "-c",
+                "--clipboard-type",
+                "text/html",
+                "--strip-html",
+            ],
+            &mut clipboard,
+        );
+        assert_eq!(stdout, "\"it\u{2019}s\u{a0}done\"\n");
+
+        // The flavour only applies to clipboard input
+        assert!(Args::try_parse_from(["quot", "--clipboard-type", "html", "in.txt"]).is_err());
+    }
+
+    #[test]
+    fn test_primary_selection_to_clipboard() {
+        // Select text, then convert it straight onto the clipboard
+        let mut clipboard = MemoryProvider::with_primary("say \"hi\"");
+        let stdout = run_with_clipboard(&["quot", "--primary", "-C"], &mut clipboard);
+
+        assert_eq!(
+            clipboard.contents(Selection::Clipboard),
+            Some("\"say \\\"hi\\\"\"")
+        );
+        assert_eq!(clipboard.contents(Selection::Primary), Some("say \"hi\""));
+        assert_eq!(stdout, "");
+    }
+
+    #[test]
+    fn test_output_to_both_selections() {
+        let mut clipboard = MemoryProvider::default();
+        let args = Args::try_parse_from(["quot", "-C", "-P"]).unwrap();
+
+        let mut stdout = Vec::new();
+        write_output(&args, "x", &mut clipboard, &mut stdout).unwrap();
+        assert_eq!(clipboard.contents(Selection::Clipboard), Some("x"));
+        assert_eq!(clipboard.contents(Selection::Primary), Some("x"));
+        assert!(stdout.is_empty());
+    }
+
+    #[test]
+    fn test_clipboard_input_to_stdout() {
+        let mut clipboard = MemoryProvider::new("a\nb");
+        let stdout = run_with_clipboard(&["quot", "-c", "-o", "lines", "-d"], &mut clipboard);
+
+        assert_eq!(
+            stdout,
+            "pub const DATA: &[&str] = &[\n    \"a\",\n    \"b\",\n];\n"
+        );
+        // Reading leaves the clipboard untouched
+        assert_eq!(clipboard.contents(Selection::Clipboard), Some("a\nb"));
+    }
+
+    #[test]
+    fn test_clipboard_output_with_echo() {
+        let mut clipboard = MemoryProvider::default();
+        let args = Args::try_parse_from(["quot", "-C", "--echo"]).unwrap();
+
+        let mut stdout = Vec::new();
+        write_output(&args, "\"x\"", &mut clipboard, &mut stdout).unwrap();
+        assert_eq!(clipboard.contents(Selection::Clipboard), Some("\"x\""));
+        assert_eq!(stdout, b"\"x\"\n");
+    }
+
+    #[test]
+    fn test_can_stream() {
+        let can_stream = |argv: &[&str]| Args::try_parse_from(argv).unwrap().can_stream();
+
+        assert!(can_stream(&["quot", "big.log"]));
+        assert!(can_stream(&["quot", "-m", "raw", "big.log"]));
+
+        // Anything that needs the whole result in memory takes the buffered path
+        assert!(!can_stream(&["quot", "-w", "80", "big.log"]));
+        assert!(!can_stream(&["quot", "-n", "LOG", "big.log"]));
+        assert!(!can_stream(&["quot", "-o", "lines", "big.log"]));
+        assert!(!can_stream(&["quot", "-C", "big.log"]));
+    }
+
+    #[test]
+    fn test_watch_arguments() {
+        let args =
+            Args::try_parse_from(["quot", "watch", "-m", "raw", "--pattern", "^SELECT"]).unwrap();
+        let Some(Command::Watch(watch_args)) = args.command else {
+            panic!("expected the watch subcommand");
+        };
+        assert!(matches!(watch_args.format, QuoteFormat::Raw));
+        assert_eq!(watch_args.interval, 250);
+        assert!(watch_args.pattern.unwrap().is_match("SELECT 1"));
+
+        // Invalid patterns are rejected while parsing
+        assert!(Args::try_parse_from(["quot", "watch", "--pattern", "("]).is_err());
+    }
+
     #[test]
     fn test_quote_styles() {
         // Test double quotes (default)
@@ -346,27 +1015,4 @@ mod tests {
         let expected_single = "'Hello \"world\"\\nTab:\\tNewline:\\nEnd'";
         assert_eq!(test_single, expected_single);
     }
-
-    #[test]
-    fn test_raw_string_delimiter() {
-        // Test simple case
-        let content1 = "Hello world";
-        let delimiter1 = find_raw_string_delimiter(content1);
-        assert_eq!(delimiter1, "#");
-
-        // Test with quotes
-        let content2 = "Hello \"world\"";
-        let delimiter2 = find_raw_string_delimiter(content2);
-        assert_eq!(delimiter2, "##"); // one more than the single quote
-
-        // Test with consecutive quotes
-        let content3 = "Has \"\"\" three quotes";
-        let delimiter3 = find_raw_string_delimiter(content3);
-        assert_eq!(delimiter3, "####"); // one more than three consecutive
-
-        // Test with no quotes
-        let content4 = "No quotes here";
-        let delimiter4 = find_raw_string_delimiter(content4);
-        assert_eq!(delimiter4, "#");
-    }
 }
@@ -0,0 +1,338 @@
+use std::{
+    collections::HashSet,
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{file_handler, format_literal, identifier, LookupStyle, NameCase, QuoteFormat};
+
+/// A file to embed, keyed by its path relative to the input it was found through
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub key: String,
+    pub path: PathBuf,
+}
+
+/// Expand directories (recursively) and glob patterns into a sorted, de-duplicated
+/// entry list. Every key is relative to the directory an input names: the
+/// directory itself, a file's parent, or the part of a glob pattern before its
+/// first wildcard. A file reached through more than one input is embedded
+/// once; two different files with the same key are an error, since only one
+/// of them could be embedded.
+pub fn collect_entries(inputs: &[String]) -> Result<Vec<Entry>, Box<dyn Error>> {
+    let mut entries = Vec::new();
+
+    for input in inputs {
+        let input_path = Path::new(input);
+        if input_path.is_dir() {
+            walk_directory(input_path, input_path, &mut HashSet::new(), &mut entries)?;
+        } else if input_path.is_file() {
+            let base = input_path.parent().unwrap_or(Path::new(""));
+            entries.push(Entry {
+                key: relative_key(base, input_path),
+                path: input_path.to_path_buf(),
+            });
+        } else {
+            let base = glob_base(input);
+            let mut matched = false;
+            for path in glob::glob(input)? {
+                let path = path?;
+                if path.is_file() {
+                    entries.push(Entry {
+                        key: relative_key(&base, &path),
+                        path,
+                    });
+                    matched = true;
+                }
+            }
+            if !matched {
+                return Err(format!("'{input}' did not match any files").into());
+            }
+        }
+    }
+
+    // The first input that reaches a file decides its key
+    let mut seen = HashSet::new();
+    entries
+        .retain(|entry| seen.insert(fs::canonicalize(&entry.path).unwrap_or(entry.path.clone())));
+
+    // Sorting by key keeps the generated module stable across file systems
+    entries.sort_by(|a, b| a.key.cmp(&b.key));
+    for pair in entries.windows(2) {
+        if pair[0].key == pair[1].key {
+            return Err(format!(
+                "'{}' and '{}' would both be embedded as '{}'",
+                pair[0].path.display(),
+                pair[1].path.display(),
+                pair[1].key
+            )
+            .into());
+        }
+    }
+    Ok(entries)
+}
+
+/// Walk `dir` in name order, entering each directory once however many
+/// symlinks lead to it, so a link back to an ancestor cannot loop
+fn walk_directory(
+    root: &Path,
+    dir: &Path,
+    visited: &mut HashSet<PathBuf>,
+    entries: &mut Vec<Entry>,
+) -> Result<(), Box<dyn Error>> {
+    if !visited.insert(fs::canonicalize(dir)?) {
+        return Ok(());
+    }
+    let mut paths = fs::read_dir(dir)?
+        .map(|dir_entry| dir_entry.map(|e| e.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    paths.sort();
+
+    for path in paths {
+        if path.is_dir() {
+            walk_directory(root, &path, visited, entries)?;
+        } else if path.is_file() {
+            entries.push(Entry {
+                key: relative_key(root, &path),
+                path,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// The directory part of a glob pattern before its first wildcard
+fn glob_base(pattern: &str) -> PathBuf {
+    Path::new(pattern)
+        .components()
+        .take_while(|component| {
+            !component
+                .as_os_str()
+                .to_string_lossy()
+                .contains(['*', '?', '['])
+        })
+        .collect()
+}
+
+/// `path` relative to `base`, with components joined by `/` so keys are
+/// identical on every platform. `.` components are ignored on both sides.
+fn relative_key(base: &Path, path: &Path) -> String {
+    let base = normal_components(base);
+    let parts = normal_components(path);
+    let relative = parts.strip_prefix(base.as_slice()).unwrap_or(&parts);
+    relative.join("/")
+}
+
+fn normal_components(path: &Path) -> Vec<String> {
+    path.components()
+        .filter_map(|component| match component {
+            std::path::Component::Normal(part) => Some(part.to_string_lossy().into_owned()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Read every entry and render the module source
+pub fn generate_module(entries: &[Entry], lookup: LookupStyle) -> Result<String, Box<dyn Error>> {
+    let mut files = Vec::with_capacity(entries.len());
+    for entry in entries {
+        let content = file_handler::read_file_content(&entry.path)
+            .map_err(|e| format!("Error reading file '{}': {e}", entry.path.display()))?;
+        files.push((entry.key.clone(), content));
+    }
+    Ok(render_module(&files, lookup))
+}
+
+/// Render `(key, content)` pairs as a Rust module with one constant per file
+pub fn render_module(files: &[(String, String)], lookup: LookupStyle) -> String {
+    let names = constant_names(
+        files.iter().map(|(key, _)| key.as_str()),
+        NameCase::UpperSnake,
+    );
+
+    let mut module = String::from("// @generated by quot. Do not edit by hand.\n");
+    for ((key, content), name) in files.iter().zip(&names) {
+        let literal = format_literal(content, QuoteFormat::Raw);
+        module.push_str(&format!(
+            "\n/// `{key}`\npub const {name}: &str = {literal};\n"
+        ));
+    }
+
+    match lookup {
+        LookupStyle::None => {}
+        LookupStyle::Match => {
+            module.push_str("\n/// Look up an embedded file by its relative path\n");
+            module.push_str("pub fn get(path: &str) -> Option<&'static str> {\n");
+            module.push_str("    match path {\n");
+            for ((key, _), name) in files.iter().zip(&names) {
+                let key = format_literal(key, QuoteFormat::Double);
+                module.push_str(&format!("        {key} => Some({name}),\n"));
+            }
+            module.push_str("        _ => None,\n    }\n}\n");
+        }
+        LookupStyle::Phf => {
+            module.push_str("\n/// Embedded files keyed by their relative path\n");
+            module.push_str(
+                "pub static FILES: phf::Map<&'static str, &'static str> = phf::phf_map! {\n",
+            );
+            for ((key, _), name) in files.iter().zip(&names) {
+                let key = format_literal(key, QuoteFormat::Double);
+                module.push_str(&format!("    {key} => {name},\n"));
+            }
+            module.push_str("};\n");
+        }
+    }
+
+    module
+}
+
+/// Derive unique constant names, suffixing later collisions with `_2`, `_3`, ...
+pub fn constant_names<'a>(keys: impl Iterator<Item = &'a str>, case: NameCase) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for key in keys {
+        let base = identifier::to_identifier(key, case);
+        let mut name = base.clone();
+        let mut suffix = 2;
+        while names.contains(&name) {
+            name = format!("{base}_{suffix}");
+            suffix += 1;
+        }
+        names.push(name);
+    }
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn files(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(key, content)| (key.to_string(), content.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_render_module_constants() {
+        let module = render_module(
+            &files(&[("users/find.sql", "SELECT \"id\" FROM users")]),
+            LookupStyle::None,
+        );
+        assert_eq!(
+            module,
+            "// @generated by quot. Do not edit by hand.\n\n/// `users/find.sql`\npub const USERS_FIND_SQL: &str = r##\"SELECT \"id\" FROM users\"##;\n"
+        );
+    }
+
+    #[test]
+    fn test_render_module_match_lookup() {
+        let module = render_module(&files(&[("a.txt", "a")]), LookupStyle::Match);
+        assert!(module.contains("pub fn get(path: &str) -> Option<&'static str> {"));
+        assert!(module.contains("        \"a.txt\" => Some(A_TXT),\n"));
+    }
+
+    #[test]
+    fn test_render_module_phf_lookup() {
+        let module = render_module(&files(&[("a.txt", "a")]), LookupStyle::Phf);
+        assert!(module.contains("phf::phf_map! {\n    \"a.txt\" => A_TXT,\n};"));
+    }
+
+    #[test]
+    fn test_constant_name_collisions() {
+        let names = constant_names(
+            ["a-b.txt", "a_b.txt", "a.b.txt"].into_iter(),
+            NameCase::UpperSnake,
+        );
+        assert_eq!(names, ["A_B_TXT", "A_B_TXT_2", "A_B_TXT_3"]);
+
+        // A suffixed name never lands on one that another key produces directly
+        let names = constant_names(
+            ["a_b_txt_2", "a-b.txt", "a.b.txt"].into_iter(),
+            NameCase::UpperSnake,
+        );
+        assert_eq!(names, ["A_B_TXT_2", "A_B_TXT", "A_B_TXT_3"]);
+    }
+
+    #[test]
+    fn test_collect_entries_is_sorted_and_relative() {
+        let root = std::env::temp_dir().join("quot_module_generator_test");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("nested")).unwrap();
+        fs::write(root.join("b.txt"), "b").unwrap();
+        fs::write(root.join("nested").join("a.txt"), "a").unwrap();
+
+        let input = root.to_string_lossy().into_owned();
+        let entries = collect_entries(&[input.clone(), input]).unwrap();
+        let keys: Vec<&str> = entries.iter().map(|e| e.key.as_str()).collect();
+        assert_eq!(keys, ["b.txt", "nested/a.txt"]);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_collect_entries_rejects_different_files_with_the_same_key() {
+        let root = std::env::temp_dir().join("quot_module_generator_clash_test");
+        let _ = fs::remove_dir_all(&root);
+        for dir in ["one", "two"] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+            fs::write(root.join(dir).join("a.txt"), dir).unwrap();
+        }
+
+        let inputs = ["one", "two"].map(|dir| root.join(dir).to_string_lossy().into_owned());
+        let error = collect_entries(&inputs).unwrap_err().to_string();
+        assert!(error.contains(&root.join("one").join("a.txt").display().to_string()));
+        assert!(error.contains(&root.join("two").join("a.txt").display().to_string()));
+        assert!(error.ends_with("would both be embedded as 'a.txt'"));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_collect_entries_keys_globs_like_directories() {
+        let root = std::env::temp_dir().join("quot_module_generator_glob_test");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("sql").join("nested")).unwrap();
+        fs::write(root.join("sql").join("a.sql"), "a").unwrap();
+        fs::write(root.join("sql").join("nested").join("b.sql"), "b").unwrap();
+
+        let dir = root.join("sql").to_string_lossy().into_owned();
+        let inputs = [
+            dir.clone(),
+            format!("{dir}/*.sql"),
+            format!("{dir}/nested/b.sql"),
+        ];
+        let entries = collect_entries(&inputs).unwrap();
+        let keys: Vec<&str> = entries.iter().map(|e| e.key.as_str()).collect();
+        assert_eq!(keys, ["a.sql", "nested/b.sql"]);
+
+        // On their own, a glob and a file are keyed from their own directory
+        let entries = collect_entries(&[format!("{dir}/**/*.sql")]).unwrap();
+        let keys: Vec<&str> = entries.iter().map(|e| e.key.as_str()).collect();
+        assert_eq!(keys, ["a.sql", "nested/b.sql"]);
+        let entries = collect_entries(&[format!("{dir}/nested/b.sql")]).unwrap();
+        assert_eq!(entries[0].key, "b.sql");
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_collect_entries_survives_a_symlink_loop() {
+        let root = std::env::temp_dir().join("quot_module_generator_loop_test");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("nested")).unwrap();
+        fs::write(root.join("a.txt"), "a").unwrap();
+        fs::write(root.join("nested").join("b.txt"), "b").unwrap();
+        std::os::unix::fs::symlink(".", root.join("loop")).unwrap();
+        std::os::unix::fs::symlink("..", root.join("nested").join("up")).unwrap();
+
+        let mut entries = Vec::new();
+        walk_directory(&root, &root, &mut HashSet::new(), &mut entries).unwrap();
+        let keys: Vec<&str> = entries.iter().map(|e| e.key.as_str()).collect();
+        assert_eq!(keys, ["a.txt", "nested/b.txt"]);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,194 @@
+use std::{
+    env,
+    error::Error,
+    fs::{File, OpenOptions},
+    io::{self, Read, Write},
+    sync::mpsc,
+    thread,
+    time::Duration,
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+
+use crate::{
+    clipboard_handler::Selection,
+    terminal_guard::{self, TerminalGuard},
+};
+
+/// How long to wait for the terminal to answer a clipboard query
+const QUERY_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// GNU screen truncates long DCS strings, so sequences are sent in pieces
+const SCREEN_CHUNK_SIZE: usize = 76;
+
+/// Terminal multiplexer that sits between quot and the real terminal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Multiplexer {
+    None,
+    Tmux,
+    Screen,
+}
+
+impl Multiplexer {
+    pub fn detect() -> Self {
+        // tmux also sets TERM=screen*, so it has to be checked first
+        if env::var_os("TMUX").is_some() {
+            Multiplexer::Tmux
+        } else if env::var("TERM").is_ok_and(|term| term.starts_with("screen")) {
+            Multiplexer::Screen
+        } else {
+            Multiplexer::None
+        }
+    }
+}
+
+/// The OSC 52 selection parameter
+fn selection_parameter(selection: Selection) -> char {
+    match selection {
+        Selection::Clipboard => 'c',
+        Selection::Primary => 'p',
+    }
+}
+
+/// Build the escape sequence that sets the selection to `text`
+pub fn copy_sequence(text: &str, selection: Selection, multiplexer: Multiplexer) -> String {
+    let parameter = selection_parameter(selection);
+    let sequence = format!("\x1b]52;{parameter};{}\x07", STANDARD.encode(text));
+    wrap_passthrough(&sequence, multiplexer)
+}
+
+/// Build the escape sequence that asks the terminal for the selection's contents
+pub fn query_sequence(selection: Selection, multiplexer: Multiplexer) -> String {
+    let parameter = selection_parameter(selection);
+    wrap_passthrough(&format!("\x1b]52;{parameter};?\x07"), multiplexer)
+}
+
+/// Wrap a sequence so the multiplexer forwards it to the outer terminal
+fn wrap_passthrough(sequence: &str, multiplexer: Multiplexer) -> String {
+    match multiplexer {
+        Multiplexer::None => sequence.to_string(),
+        // tmux needs every ESC inside the passthrough doubled
+        Multiplexer::Tmux => format!("\x1bPtmux;{}\x1b\\", sequence.replace('\x1b', "\x1b\x1b")),
+        Multiplexer::Screen => sequence
+            .as_bytes()
+            .chunks(SCREEN_CHUNK_SIZE)
+            .map(|chunk| format!("\x1bP{}\x1b\\", String::from_utf8_lossy(chunk)))
+            .collect(),
+    }
+}
+
+/// Extract the clipboard text from a terminal's reply to the query sequence
+pub fn parse_query_response(response: &[u8]) -> Option<String> {
+    let response = String::from_utf8_lossy(response);
+    let start = response.find("]52;")? + 4;
+    let rest = &response[start..];
+    // Skip the selection parameter, e.g. `c;`
+    let payload = &rest[rest.find(';')? + 1..];
+    let end = payload.find(['\x07', '\x1b'])?;
+    let decoded = STANDARD.decode(&payload[..end]).ok()?;
+    String::from_utf8(decoded).ok()
+}
+
+/// Open the controlling terminal so the sequences reach it even when stdout is redirected
+fn open_tty() -> io::Result<File> {
+    OpenOptions::new().read(true).write(true).open("/dev/tty")
+}
+
+/// Set the local terminal's clipboard through an OSC 52 escape sequence
+pub fn write_clipboard(text: &str, selection: Selection) -> Result<(), Box<dyn Error>> {
+    let sequence = copy_sequence(text, selection, Multiplexer::detect());
+    let mut tty = terminal_guard::terminal_output();
+    tty.write_all(sequence.as_bytes())?;
+    tty.flush()?;
+    Ok(())
+}
+
+/// Ask the terminal for its clipboard contents. Many terminals disable this
+/// for security reasons, in which case the query times out.
+pub fn read_clipboard(selection: Selection) -> Result<String, Box<dyn Error>> {
+    let mut tty = open_tty()?;
+    let mut reader = tty.try_clone()?;
+
+    // Without raw mode the reply would be echoed and held back until a newline
+    let _terminal = TerminalGuard::enter_raw_mode()?;
+    tty.write_all(query_sequence(selection, Multiplexer::detect()).as_bytes())?;
+    tty.flush()?;
+
+    // The read blocks until the terminal answers, so run it on a thread we can abandon
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let mut response = Vec::new();
+        let mut byte = [0u8; 1];
+        while reader.read(&mut byte).is_ok_and(|n| n == 1) {
+            response.push(byte[0]);
+            if byte[0] == b'\x07' || response.ends_with(b"\x1b\\") {
+                break;
+            }
+        }
+        let _ = sender.send(response);
+    });
+
+    let response = receiver
+        .recv_timeout(QUERY_TIMEOUT)
+        .map_err(|_| "the terminal did not answer the OSC 52 clipboard query")?;
+    parse_query_response(&response)
+        .ok_or_else(|| "the terminal sent an unreadable OSC 52 reply".into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_copy_sequence() {
+        let sequence = copy_sequence("Hi", Selection::Clipboard, Multiplexer::None);
+        assert_eq!(sequence, "\x1b]52;c;SGk=\x07");
+    }
+
+    #[test]
+    fn test_tmux_passthrough_doubles_escapes() {
+        let sequence = copy_sequence("Hi", Selection::Clipboard, Multiplexer::Tmux);
+        assert_eq!(sequence, "\x1bPtmux;\x1b\x1b]52;c;SGk=\x07\x1b\\");
+    }
+
+    #[test]
+    fn test_screen_passthrough_is_chunked() {
+        let text = "x".repeat(200);
+        let sequence = copy_sequence(&text, Selection::Clipboard, Multiplexer::Screen);
+        assert!(sequence.starts_with("\x1bP\x1b]52;c;"));
+        assert_eq!(sequence.matches("\x1bP").count(), 4);
+        assert!(sequence.ends_with("\x07\x1b\\"));
+    }
+
+    #[test]
+    fn test_query_sequence() {
+        assert_eq!(
+            query_sequence(Selection::Clipboard, Multiplexer::None),
+            "\x1b]52;c;?\x07"
+        );
+    }
+
+    #[test]
+    fn test_primary_selection_parameter() {
+        let sequence = copy_sequence("Hi", Selection::Primary, Multiplexer::None);
+        assert_eq!(sequence, "\x1b]52;p;SGk=\x07");
+        assert_eq!(
+            query_sequence(Selection::Primary, Multiplexer::None),
+            "\x1b]52;p;?\x07"
+        );
+    }
+
+    #[test]
+    fn test_parse_query_response() {
+        assert_eq!(
+            parse_query_response(b"\x1b]52;c;SGVsbG8=\x07"),
+            Some("Hello".to_string())
+        );
+        // String terminator instead of BEL
+        assert_eq!(
+            parse_query_response(b"\x1b]52;c;SGk=\x1b\\"),
+            Some("Hi".to_string())
+        );
+        assert_eq!(parse_query_response(b"garbage"), None);
+    }
+}
@@ -0,0 +1,198 @@
+use std::error::Error;
+
+use crate::{
+    array_formatter, byte_array_formatter,
+    declaration_formatter::{self, ValueKind},
+    encoding_formatter::{self, Encoding},
+    format_literal, identifier, literal_splitter, Language, NameCase, OutputMode, QuoteFormat,
+};
+
+/// Settings shared by every output mode
+#[derive(Debug, Clone)]
+pub struct RenderOptions {
+    pub format: QuoteFormat,
+    pub output: OutputMode,
+    pub lang: Language,
+    /// Split literals into concatenated chunks no wider than this
+    pub width: Option<usize>,
+    pub bytes_per_line: usize,
+    pub keep_newlines: bool,
+    pub skip_blank_lines: bool,
+    /// Wrap base64/hex output in a decode expression
+    pub decode: bool,
+    /// Case for declared names; `None` follows the language's convention
+    pub name_case: Option<NameCase>,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        RenderOptions {
+            format: QuoteFormat::Double,
+            output: OutputMode::Literal,
+            lang: Language::Rust,
+            width: None,
+            bytes_per_line: 12,
+            keep_newlines: false,
+            skip_blank_lines: false,
+            decode: false,
+            name_case: None,
+        }
+    }
+}
+
+impl RenderOptions {
+    /// Turn a raw name into an identifier following the configured case, clear
+    /// of the language's reserved words
+    pub fn identifier(&self, raw_name: &str) -> String {
+        let case = self
+            .name_case
+            .unwrap_or_else(|| identifier::default_case(self.lang));
+        identifier::escape_keyword(identifier::to_identifier(raw_name, case), self.lang)
+    }
+}
+
+/// Render arbitrary content in the configured output mode. With a name the
+/// result is a full declaration, otherwise a bare value.
+pub fn render(
+    content: &[u8],
+    name: Option<&str>,
+    options: &RenderOptions,
+) -> Result<String, Box<dyn Error>> {
+    match options.output {
+        OutputMode::Literal => render_text(std::str::from_utf8(content)?, name, options),
+        OutputMode::Lines => {
+            let text = std::str::from_utf8(content)?;
+            let lines = array_formatter::split_lines(text, options.keep_newlines);
+            render_lines(lines, name, options)
+        }
+        OutputMode::Bytes | OutputMode::Base64 | OutputMode::Hex => {
+            render_bytes(content, name, options)
+        }
+    }
+}
+
+/// Render text as a (possibly split) string literal
+pub fn render_text(
+    input: &str,
+    name: Option<&str>,
+    options: &RenderOptions,
+) -> Result<String, Box<dyn Error>> {
+    let literal = render_literal(input, options);
+    declare(literal, ValueKind::Str, name, options)
+}
+
+/// Render lines as an array or list literal
+pub fn render_lines(
+    mut lines: Vec<String>,
+    name: Option<&str>,
+    options: &RenderOptions,
+) -> Result<String, Box<dyn Error>> {
+    if options.skip_blank_lines {
+        lines = array_formatter::remove_blank_lines(lines);
+    }
+    let array = array_formatter::format_array(&lines, options.format, options.lang, name.is_some());
+    declare(array, ValueKind::StrList, name, options)
+}
+
+/// Render bytes as a byte array or an encoded literal
+pub fn render_bytes(
+    bytes: &[u8],
+    name: Option<&str>,
+    options: &RenderOptions,
+) -> Result<String, Box<dyn Error>> {
+    let encoding = match options.output {
+        OutputMode::Base64 => Encoding::Base64,
+        OutputMode::Hex => Encoding::Hex,
+        OutputMode::Bytes => {
+            // Byte arrays are always emitted as a declaration
+            let name = options.identifier(name.unwrap_or(identifier::DEFAULT_NAME));
+            return byte_array_formatter::format_byte_array(
+                bytes,
+                options.lang,
+                &name,
+                options.bytes_per_line,
+            );
+        }
+        OutputMode::Literal | OutputMode::Lines => return render(bytes, name, options),
+    };
+
+    let encoded = encoding_formatter::encode(bytes, encoding);
+    let literal = render_literal(&encoded, options);
+    if options.decode {
+        let expression = encoding_formatter::wrap_decode(&literal, encoding, options.lang)?;
+        declare(expression, ValueKind::Decoded, name, options)
+    } else {
+        declare(literal, ValueKind::Str, name, options)
+    }
+}
+
+fn render_literal(input: &str, options: &RenderOptions) -> String {
+    match options.width {
+        Some(width) => literal_splitter::split_literal(input, options.format, options.lang, width),
+        None => format_literal(input, options.format),
+    }
+}
+
+fn declare(
+    value: String,
+    kind: ValueKind,
+    name: Option<&str>,
+    options: &RenderOptions,
+) -> Result<String, Box<dyn Error>> {
+    match name {
+        Some(raw_name) => declaration_formatter::format_declaration(
+            &options.identifier(raw_name),
+            &value,
+            kind,
+            options.lang,
+        ),
+        None => Ok(value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_bare_literal() {
+        let output = render(b"a\"b", None, &RenderOptions::default()).unwrap();
+        assert_eq!(output, "\"a\\\"b\"");
+    }
+
+    #[test]
+    fn test_render_declaration() {
+        let options = RenderOptions {
+            format: QuoteFormat::Raw,
+            ..RenderOptions::default()
+        };
+        let output = render(b"x", Some("my-query.sql"), &options).unwrap();
+        assert_eq!(output, "pub const MY_QUERY_SQL: &str = r#\"x\"#;");
+    }
+
+    #[test]
+    fn test_render_dispatches_on_output_mode() {
+        let options = RenderOptions {
+            output: OutputMode::Lines,
+            lang: Language::Python,
+            skip_blank_lines: true,
+            ..RenderOptions::default()
+        };
+        let output = render(b"a\n\nb\n", None, &options).unwrap();
+        assert_eq!(output, "[\n    \"a\",\n    \"b\",\n]");
+
+        let options = RenderOptions {
+            output: OutputMode::Hex,
+            lang: Language::Python,
+            decode: true,
+            ..RenderOptions::default()
+        };
+        let output = render(&[0xde, 0xad], Some("blob"), &options).unwrap();
+        assert_eq!(output, "BLOB = bytes.fromhex(\"dead\")");
+    }
+
+    #[test]
+    fn test_render_rejects_invalid_utf8_for_text_modes() {
+        assert!(render(&[0xff], None, &RenderOptions::default()).is_err());
+    }
+}
@@ -1,285 +1,398 @@
-use std::io::{self, Write};
+use std::{
+    borrow::Cow,
+    error::Error,
+    fmt,
+    io::{self, BufWriter, Write},
+};
 
 use crossterm::{
     cursor,
-    event::{KeyCode, KeyEvent, KeyModifiers},
+    event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    queue,
     terminal::{self, ClearType},
-    ExecutableCommand,
 };
 
-pub fn enable_raw_mode() -> Result<(), io::Error> {
-    terminal::enable_raw_mode().map_err(io::Error::other)
+use crate::{
+    line_editor::LineEditor,
+    terminal_guard, unicode_text,
+    vi_mode::{self, EditingMode, ViState},
+};
+
+fn get_prompt_width(line_number: usize) -> usize {
+    // Calculate width: line number + "> " (or whichever mark the mode shows)
+    // Use right-aligned format like Screen::render
+    format!("{line_number:2}> ").len()
 }
 
-pub fn disable_raw_mode() -> Result<(), io::Error> {
-    terminal::disable_raw_mode().map_err(io::Error::other)
+/// Tabs are drawn as spaces up to the next multiple of this many columns
+const TAB_WIDTH: usize = 4;
+
+/// How a line is drawn: tabs expand to spaces and other control characters
+/// (such as the `\r` of a pasted CRLF line ending) appear in caret notation,
+/// so the terminal never acts on them and every column is accounted for
+pub fn visible_text(line: &str) -> String {
+    let mut visible = String::with_capacity(line.len());
+    let mut column = 0;
+
+    for grapheme in unicode_text::graphemes(line) {
+        let start = visible.len();
+        if grapheme == "\t" {
+            let spaces = TAB_WIDTH - column % TAB_WIDTH;
+            visible.extend(std::iter::repeat_n(' ', spaces));
+        } else if grapheme.chars().any(char::is_control) {
+            for c in grapheme.chars() {
+                match u8::try_from(c) {
+                    Ok(byte) if byte < 0x20 || byte == 0x7f => {
+                        visible.push('^');
+                        visible.push(char::from(byte ^ 0x40));
+                    }
+                    _ if c.is_control() => visible.push(char::REPLACEMENT_CHARACTER),
+                    _ => visible.push(c),
+                }
+            }
+        } else {
+            visible.push_str(grapheme);
+        }
+        column += unicode_text::display_width(&visible[start..]);
+    }
+    visible
 }
 
-pub fn print_prompt_with_line_number(line_number: usize) -> Result<(), io::Error> {
-    print!("{line_number:2}> ");
-    io::stdout().flush()
+/// Screen column of grapheme index `cursor`, counting the prompt
+pub fn cursor_column(line: &str, cursor: usize, line_number: usize) -> usize {
+    let offset = unicode_text::byte_offset(line, cursor);
+    get_prompt_width(line_number) + unicode_text::display_width(&visible_text(&line[..offset]))
 }
 
-fn get_prompt_width(line_number: usize) -> usize {
-    // Calculate width: line number + "> "
-    // Use right-aligned format like print_prompt_with_line_number
-    format!("{line_number:2}> ").len()
+/// Width of the terminal, assuming 80 columns when it reports none (e.g. a bare pty)
+fn terminal_columns() -> usize {
+    match terminal::size() {
+        Ok((columns, _)) if columns > 0 => usize::from(columns),
+        _ => 80,
+    }
 }
 
-pub fn move_to_next_line() -> Result<(), io::Error> {
-    io::stdout()
-        .execute(cursor::MoveToColumn(0))
-        .map_err(io::Error::other)?;
-    println!();
-    Ok(())
+fn to_u16(value: usize) -> u16 {
+    u16::try_from(value).unwrap_or(u16::MAX)
 }
 
-pub fn handle_key_event(
-    event: KeyEvent,
-    current_line: &mut String,
-    builder: &mut Vec<String>,
-    cursor_pos: &mut usize,
-) -> Result<bool, io::Error> {
-    let current_line_number = builder.len() + 1;
+/// Draws the editor's buffer, one numbered prompt per line, starting at the
+/// row where interactive input began. Every change redraws the whole buffer,
+/// so edits on earlier lines and long lines wrapping across rows stay correct.
+/// Drawing goes to the terminal itself, leaving stdout for the result.
+pub struct Screen {
+    output: BufWriter<Box<dyn Write>>,
+    /// Terminal rows between the first row of the buffer and the cursor
+    cursor_row: usize,
+    /// Terminal rows between the first row of the buffer and its last row
+    last_row: usize,
+    /// Drawn after each line number; shows the vi mode
+    prompt_mark: char,
+}
 
-    match event.code {
-        KeyCode::Enter => {
-            move_to_next_line()?;
+impl Screen {
+    pub fn new() -> Self {
+        Screen {
+            output: BufWriter::new(terminal_guard::terminal_output()),
+            cursor_row: 0,
+            last_row: 0,
+            prompt_mark: '>',
+        }
+    }
 
-            if current_line.trim().is_empty() {
-                return Ok(true); // Signal to exit
-            }
+    /// Change the mark drawn after each line number, from the next render on
+    pub fn set_prompt_mark(&mut self, mark: char) {
+        self.prompt_mark = mark;
+    }
+
+    /// Print a line of help above the prompt
+    pub fn print_hint(&mut self, hint: &str) -> Result<(), io::Error> {
+        write!(self.output, "{hint}\r\n")?;
+        self.output.flush()
+    }
 
-            builder.push(current_line.clone());
-            current_line.clear();
-            *cursor_pos = 0;
-            // Print prompt with line number (current line count + 1)
-            print_prompt_with_line_number(builder.len() + 1)?;
+    pub fn render(&mut self, editor: &LineEditor) -> Result<(), io::Error> {
+        let output = &mut self.output;
+        let columns = terminal_columns();
+        let (cursor_line, cursor_grapheme) = editor.cursor();
+
+        if self.cursor_row > 0 {
+            queue!(output, cursor::MoveUp(to_u16(self.cursor_row)))?;
         }
-        KeyCode::Char(c) => {
-            // Handle Ctrl+C to exit
-            if c == 'c' && event.modifiers.contains(KeyModifiers::CONTROL) {
-                return Ok(true); // Signal to exit
+        queue!(
+            output,
+            cursor::MoveToColumn(0),
+            terminal::Clear(ClearType::FromCursorDown)
+        )?;
+
+        let mut row = 0;
+        let mut cursor_position = (0, 0);
+        for (index, line) in editor.lines().iter().enumerate() {
+            let line_number = index + 1;
+            if index > 0 {
+                write!(output, "\r\n")?;
+                row += 1;
             }
-            insert_char_at_cursor(current_line, cursor_pos, c, current_line_number)?;
-        }
-        KeyCode::Backspace => {
-            delete_char_at_cursor(current_line, cursor_pos, current_line_number)?;
-        }
-        KeyCode::Delete => {
-            delete_char_forward(current_line, cursor_pos, current_line_number)?;
-        }
-        KeyCode::Left => {
-            move_cursor_left(cursor_pos, current_line_number)?;
-        }
-        KeyCode::Right => {
-            move_cursor_right(current_line, cursor_pos, current_line_number)?;
-        }
-        KeyCode::Home => {
-            move_cursor_to_start(cursor_pos, current_line_number)?;
+            let visible = visible_text(line);
+            write!(output, "{line_number:2}{} {visible}", self.prompt_mark)?;
+
+            if index == cursor_line {
+                let column = cursor_column(line, cursor_grapheme, line_number);
+                cursor_position = (row + column / columns, column % columns);
+            }
+
+            let width = get_prompt_width(line_number) + unicode_text::display_width(&visible);
+            if width.is_multiple_of(columns) {
+                // A line exactly filling the last row leaves the terminal waiting
+                // to wrap; force the wrap so row arithmetic stays uniform
+                write!(output, " \r")?;
+            }
+            row += width / columns;
         }
-        KeyCode::End => {
-            move_cursor_to_end(current_line, cursor_pos, current_line_number)?;
+
+        let (cursor_row, cursor_col) = cursor_position;
+        if row > cursor_row {
+            queue!(output, cursor::MoveUp(to_u16(row - cursor_row)))?;
         }
-        _ => {
-            // Ignore other keys
+        queue!(output, cursor::MoveToColumn(to_u16(cursor_col)))?;
+        self.cursor_row = cursor_row;
+        self.last_row = row;
+        output.flush()
+    }
+
+    /// Clear the whole terminal, so the next render starts at the top
+    pub fn clear(&mut self) -> Result<(), io::Error> {
+        queue!(
+            self.output,
+            terminal::Clear(ClearType::All),
+            cursor::MoveTo(0, 0)
+        )?;
+        self.cursor_row = 0;
+        self.last_row = 0;
+        self.output.flush()
+    }
+
+    /// Leave the cursor on a fresh line below the buffer
+    pub fn finish(&mut self) -> Result<(), io::Error> {
+        let output = &mut self.output;
+        if self.last_row > self.cursor_row {
+            queue!(
+                output,
+                cursor::MoveDown(to_u16(self.last_row - self.cursor_row))
+            )?;
         }
+        write!(output, "\r\n")?;
+        self.cursor_row = 0;
+        self.last_row = 0;
+        output.flush()
     }
-    Ok(false) // Continue processing
 }
 
-pub fn insert_char_at_cursor(
-    current_line: &mut String,
-    cursor_pos: &mut usize,
-    c: char,
-    line_number: usize,
-) -> Result<(), io::Error> {
-    let insert_pos = *cursor_pos;
-    current_line.insert(insert_pos, c);
-    *cursor_pos += 1;
+/// How interactive input behaves
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct InputOptions {
+    /// Finish input at the first blank line instead of keeping it as content
+    pub submit_on_blank_line: bool,
+    /// Turn CRLF and lone CR line endings in pasted text into LF
+    pub normalize_line_endings: bool,
+    pub editing_mode: EditingMode,
+}
+
+/// Interactive input was cancelled with Ctrl+C; nothing should be output
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cancelled;
 
-    // Redraw from the insertion point, but cursor should end up after the inserted char
-    redraw_line_after_insert(current_line, insert_pos, *cursor_pos, line_number)
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("input cancelled")
+    }
 }
 
-pub fn redraw_line_after_insert(
-    current_line: &str,
-    redraw_from: usize,
-    final_cursor_pos: usize,
-    line_number: usize,
-) -> Result<(), io::Error> {
-    // Move to where we need to start redrawing (the insertion point)
-    let prompt_width = get_prompt_width(line_number);
-    let redraw_col = prompt_width + redraw_from;
-
-    io::stdout()
-        .execute(cursor::MoveToColumn(redraw_col as u16))
-        .map_err(io::Error::other)?;
-
-    // Clear from current position to end of line
-    io::stdout()
-        .execute(terminal::Clear(ClearType::UntilNewLine))
-        .map_err(io::Error::other)?;
-
-    // Print the rest of the line from the redraw point
-    let rest_of_line = &current_line[redraw_from..];
-    print!("{rest_of_line}");
-
-    // Move cursor to the final position (after the inserted character)
-    let final_col = prompt_width + final_cursor_pos;
-    io::stdout()
-        .execute(cursor::MoveToColumn(final_col as u16))
-        .map_err(io::Error::other)?;
-
-    io::stdout().flush()
+impl Error for Cancelled {}
+
+/// Exit status for input cancelled by Ctrl+C, as a shell reports a process killed by SIGINT
+pub const CANCELLED_EXIT_CODE: i32 = 130;
+
+/// What the input loop should do after a key press
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyOutcome {
+    /// The buffer or cursor changed and needs redrawing
+    Edited,
+    /// The key has no binding
+    Ignored,
+    /// Input is complete
+    Submit,
+    /// Input was cancelled
+    Abort,
+    /// Stop the process in the background, as Ctrl+Z does in cooked mode
+    Suspend,
+    /// Clear the terminal and draw the buffer again at the top
+    ClearScreen,
 }
 
-pub fn delete_char_at_cursor(
-    current_line: &mut String,
-    cursor_pos: &mut usize,
-    line_number: usize,
-) -> Result<(), io::Error> {
-    if *cursor_pos > 0 && !current_line.is_empty() {
-        *cursor_pos -= 1;
-        current_line.remove(*cursor_pos);
-        redraw_line_from_cursor(current_line, *cursor_pos, line_number)
-    } else {
-        Ok(())
+/// Apply a key press to the editor without drawing anything. Besides the
+/// arrows and editing keys, the usual readline (Emacs) bindings work.
+pub fn apply_key(event: KeyEvent, editor: &mut LineEditor, options: InputOptions) -> KeyOutcome {
+    // Some platforms report key releases as well as presses
+    if event.kind == KeyEventKind::Release {
+        return KeyOutcome::Ignored;
     }
-}
+    let control = event.modifiers.contains(KeyModifiers::CONTROL);
+    let alt = event.modifiers.contains(KeyModifiers::ALT);
 
-pub fn delete_char_forward(
-    current_line: &mut String,
-    cursor_pos: &mut usize,
-    line_number: usize,
-) -> Result<(), io::Error> {
-    if *cursor_pos < current_line.len() {
-        current_line.remove(*cursor_pos);
-        redraw_line_from_cursor(current_line, *cursor_pos, line_number)
-    } else {
-        Ok(())
+    match event.code {
+        // AltGr arrives as Ctrl+Alt on Windows, and types a character
+        KeyCode::Char(c) if control && alt => editor.insert_char(c),
+        KeyCode::Char(c) if control => return apply_control_key(c, editor),
+        KeyCode::Char(c) if alt => return apply_alt_key(c, editor),
+        // Ctrl+Enter is only distinguishable on terminals with keyboard enhancements
+        KeyCode::Enter if control => return KeyOutcome::Submit,
+        KeyCode::Enter => {
+            if options.submit_on_blank_line && editor.current_line().trim().is_empty() {
+                editor.remove_line();
+                return KeyOutcome::Submit;
+            }
+            editor.split_line();
+        }
+        KeyCode::Char(c) => editor.insert_char(c),
+        // Terminals without bracketed paste send a pasted tab as a key press
+        KeyCode::Tab => editor.insert_char('\t'),
+        KeyCode::Backspace if alt => editor.kill_word_backward(),
+        KeyCode::Backspace => editor.backspace(),
+        KeyCode::Delete => editor.delete(),
+        KeyCode::Left => editor.move_left(),
+        KeyCode::Right => editor.move_right(),
+        KeyCode::Up => editor.move_up(),
+        KeyCode::Down => editor.move_down(),
+        KeyCode::Home => editor.move_to_line_start(),
+        KeyCode::End => editor.move_to_line_end(),
+        _ => return KeyOutcome::Ignored,
     }
+    KeyOutcome::Edited
 }
 
-pub fn move_cursor_left(cursor_pos: &mut usize, _line_number: usize) -> Result<(), io::Error> {
-    if *cursor_pos > 0 {
-        *cursor_pos -= 1;
-        io::stdout()
-            .execute(cursor::MoveLeft(1))
-            .map_err(io::Error::other)?;
+fn apply_control_key(c: char, editor: &mut LineEditor) -> KeyOutcome {
+    match c {
+        'd' => return KeyOutcome::Submit,
+        'c' => return KeyOutcome::Abort,
+        'z' => return KeyOutcome::Suspend,
+        'l' => return KeyOutcome::ClearScreen,
+        'a' => editor.move_to_line_start(),
+        'e' => editor.move_to_line_end(),
+        'b' => editor.move_left(),
+        'f' => editor.move_right(),
+        'p' => editor.move_up(),
+        'n' => editor.move_down(),
+        'w' => editor.kill_blank_word_backward(),
+        'k' => editor.kill_to_line_end(),
+        'u' => editor.kill_to_line_start(),
+        'y' => editor.yank(),
+        't' => editor.transpose(),
+        // Ctrl+_ arrives as Ctrl+7 from most terminals, and Ctrl+/ sends the same
+        '_' | '7' | '/' => {
+            editor.undo();
+        }
+        // Only terminals with keyboard enhancements tell Ctrl+Shift+Z from Ctrl+Z
+        'Z' => {
+            editor.redo();
+        }
+        _ => return KeyOutcome::Ignored,
     }
-    Ok(())
+    KeyOutcome::Edited
 }
 
-pub fn move_cursor_right(
-    current_line: &str,
-    cursor_pos: &mut usize,
-    _line_number: usize,
-) -> Result<(), io::Error> {
-    if *cursor_pos < current_line.len() {
-        *cursor_pos += 1;
-        io::stdout()
-            .execute(cursor::MoveRight(1))
-            .map_err(io::Error::other)?;
+fn apply_alt_key(c: char, editor: &mut LineEditor) -> KeyOutcome {
+    match c {
+        'b' => editor.move_word_left(),
+        'f' => editor.move_word_right(),
+        'd' => editor.kill_word_forward(),
+        'y' => editor.yank_pop(),
+        '/' => {
+            editor.redo();
+        }
+        _ => return KeyOutcome::Ignored,
     }
-    Ok(())
+    KeyOutcome::Edited
 }
 
-pub fn move_cursor_to_start(cursor_pos: &mut usize, line_number: usize) -> Result<(), io::Error> {
-    // Move to beginning of line (after the prompt)
-    let prompt_width = get_prompt_width(line_number);
-    io::stdout()
-        .execute(cursor::MoveToColumn(prompt_width as u16))
-        .map_err(io::Error::other)?;
-    *cursor_pos = 0;
-    Ok(())
+/// The key bindings in use, with whatever they keep between key presses
+#[derive(Debug, Clone)]
+pub enum Keymap {
+    Emacs,
+    Vi(ViState),
 }
 
-pub fn move_cursor_to_end(
-    current_line: &str,
-    cursor_pos: &mut usize,
-    line_number: usize,
-) -> Result<(), io::Error> {
-    let prompt_width = get_prompt_width(line_number);
-    let target_pos = prompt_width + current_line.len();
-    io::stdout()
-        .execute(cursor::MoveToColumn(target_pos as u16))
-        .map_err(io::Error::other)?;
-    *cursor_pos = current_line.len();
-    Ok(())
+impl Keymap {
+    pub fn new(mode: EditingMode) -> Self {
+        match mode {
+            EditingMode::Emacs => Keymap::Emacs,
+            EditingMode::Vi => Keymap::Vi(ViState::default()),
+        }
+    }
+
+    pub fn prompt_mark(&self) -> char {
+        match self {
+            Keymap::Emacs => '>',
+            Keymap::Vi(state) => state.prompt_mark(),
+        }
+    }
+
+    pub fn apply_key(
+        &mut self,
+        event: KeyEvent,
+        editor: &mut LineEditor,
+        options: InputOptions,
+    ) -> KeyOutcome {
+        match self {
+            Keymap::Emacs => apply_key(event, editor, options),
+            Keymap::Vi(state) => vi_mode::apply_key(event, editor, state, options),
+        }
+    }
 }
 
-pub fn redraw_line_from_cursor(
-    current_line: &str,
-    cursor_pos: usize,
-    line_number: usize,
-) -> Result<(), io::Error> {
-    // Move to the position where we need to start redrawing (after prompt)
-    let prompt_width = get_prompt_width(line_number);
-    let redraw_start_col = prompt_width + cursor_pos;
-
-    io::stdout()
-        .execute(cursor::MoveToColumn(redraw_start_col as u16))
-        .map_err(io::Error::other)?;
-
-    // Clear from current position to end of line
-    io::stdout()
-        .execute(terminal::Clear(ClearType::UntilNewLine))
-        .map_err(io::Error::other)?;
-
-    // Print the rest of the line from cursor position
-    let rest_of_line = &current_line[cursor_pos..];
-    print!("{rest_of_line}");
-
-    // Move cursor to the correct final position (where the cursor should be after the edit)
-    let final_col = prompt_width + cursor_pos;
-    io::stdout()
-        .execute(cursor::MoveToColumn(final_col as u16))
-        .map_err(io::Error::other)?;
-
-    io::stdout().flush()
+pub fn handle_key_event(
+    event: KeyEvent,
+    editor: &mut LineEditor,
+    keymap: &mut Keymap,
+    screen: &mut Screen,
+    options: InputOptions,
+) -> Result<KeyOutcome, io::Error> {
+    let outcome = keymap.apply_key(event, editor, options);
+    screen.set_prompt_mark(keymap.prompt_mark());
+    match outcome {
+        KeyOutcome::Edited => screen.render(editor)?,
+        KeyOutcome::ClearScreen => {
+            screen.clear()?;
+            screen.render(editor)?;
+        }
+        _ => {}
+    }
+    Ok(outcome)
 }
 
+/// Insert pasted text at the cursor exactly as it was copied, splitting the
+/// current line around it if it spans several lines
 pub fn handle_paste_event(
-    text: String,
-    current_line: &mut String,
-    builder: &mut Vec<String>,
-    cursor_pos: &mut usize,
+    text: &str,
+    editor: &mut LineEditor,
+    screen: &mut Screen,
+    options: InputOptions,
 ) -> Result<(), io::Error> {
-    let lines: Vec<&str> = text.lines().collect();
-
-    for (i, line) in lines.iter().enumerate() {
-        let trimmed_line = line.trim_start();
-        let current_line_number = builder.len() + 1;
-
-        if i == 0 {
-            // First line continues current input at cursor position
-            current_line.insert_str(*cursor_pos, trimmed_line);
-            *cursor_pos += trimmed_line.len();
-            redraw_line_from_cursor(
-                current_line,
-                *cursor_pos - trimmed_line.len(),
-                current_line_number,
-            )?;
-        } else {
-            // Additional lines
-            move_to_next_line()?;
-            if !current_line.trim().is_empty() {
-                builder.push(current_line.clone());
-            }
-            *current_line = trimmed_line.to_string();
-            *cursor_pos = trimmed_line.len();
-            let new_line_number = builder.len() + 1;
-            print_prompt_with_line_number(new_line_number)?;
-            print!("{trimmed_line}");
-        }
+    editor.insert_str(&paste_text(text, options));
+    screen.render(editor)
+}
+
+fn paste_text(text: &str, options: InputOptions) -> Cow<'_, str> {
+    if options.normalize_line_endings && text.contains('\r') {
+        Cow::Owned(text.replace("\r\n", "\n").replace('\r', "\n"))
+    } else {
+        Cow::Borrowed(text)
     }
-    io::stdout().flush()
 }
 
 #[cfg(test)]
 mod tests {
-    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use super::*;
 
     #[test]
     fn test_insert_char_at_cursor() {
@@ -368,25 +481,31 @@ mod tests {
 
     #[test]
     fn test_handle_key_event_enter() {
-        let mut current_line = String::from("test line");
-        let mut builder = Vec::<String>::new();
-        let mut cursor_pos = 9;
-
-        // Simulate Enter key behavior
-        if !current_line.trim().is_empty() {
-            builder.push(current_line.clone());
-            current_line.clear();
-            cursor_pos = 0;
-        }
-
-        assert_eq!(builder.len(), 1);
-        assert_eq!(builder[0], "test line");
-        assert_eq!(current_line, "");
-        assert_eq!(cursor_pos, 0);
-
-        // Test empty line (should signal exit)
-        let empty_line = String::new();
-        assert!(empty_line.trim().is_empty());
+        let enter = key(KeyCode::Enter, KeyModifiers::NONE);
+        let mut editor = LineEditor::new();
+        type_text(&mut editor, "test line", InputOptions::default());
+        assert_eq!(
+            apply_key(enter, &mut editor, InputOptions::default()),
+            KeyOutcome::Edited
+        );
+        assert_eq!(editor.lines(), ["test line", ""]);
+        assert_eq!(editor.cursor(), (1, 0));
+
+        // A blank line is content: Enter splits it like any other line
+        assert_eq!(
+            apply_key(enter, &mut editor, InputOptions::default()),
+            KeyOutcome::Edited
+        );
+        assert_eq!(editor.lines(), ["test line", "", ""]);
+        assert_eq!(editor.cursor(), (2, 0));
+
+        // ...unless submitting on a blank line was asked for
+        let options = InputOptions {
+            submit_on_blank_line: true,
+            ..InputOptions::default()
+        };
+        assert_eq!(apply_key(enter, &mut editor, options), KeyOutcome::Submit);
+        assert_eq!(editor.lines(), ["test line", ""]);
     }
 
     #[test]
@@ -399,8 +518,180 @@ mod tests {
         assert_eq!(lines[1], "line2");
         assert_eq!(lines[2], "line3");
 
-        // Test trimming logic
-        let trimmed_line = "  indented line  ".trim_start();
-        assert_eq!(trimmed_line, "indented line  ");
+        // Pasted indentation is kept
+        let indented = "  indented line  ";
+        assert_eq!(paste_text(indented, InputOptions::default()), indented);
+    }
+
+    #[test]
+    fn test_cursor_column_uses_display_width() {
+        // Prompt " 1> " is four columns
+        assert_eq!(cursor_column("日本語", 2, 1), 8);
+        assert_eq!(cursor_column("e\u{301}x", 1, 1), 5);
+        assert_eq!(cursor_column("abc", 10, 1), 7);
+    }
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    fn type_text(editor: &mut LineEditor, text: &str, options: InputOptions) -> KeyOutcome {
+        let mut outcome = KeyOutcome::Ignored;
+        for c in text.chars() {
+            let code = if c == '\n' {
+                KeyCode::Enter
+            } else {
+                KeyCode::Char(c)
+            };
+            outcome = apply_key(key(code, KeyModifiers::NONE), editor, options);
+        }
+        outcome
+    }
+
+    #[test]
+    fn test_blank_lines_are_content() {
+        let mut editor = LineEditor::new();
+        let outcome = type_text(
+            &mut editor,
+            "para one\n\n   \npara two",
+            InputOptions::default(),
+        );
+        assert_eq!(outcome, KeyOutcome::Edited);
+
+        let ctrl_d = key(KeyCode::Char('d'), KeyModifiers::CONTROL);
+        assert_eq!(
+            apply_key(ctrl_d, &mut editor, InputOptions::default()),
+            KeyOutcome::Submit
+        );
+        assert_eq!(editor.text(), "para one\n\n   \npara two");
+    }
+
+    #[test]
+    fn test_ctrl_enter_submits() {
+        let mut editor = LineEditor::new();
+        type_text(&mut editor, "a\n", InputOptions::default());
+        let ctrl_enter = key(KeyCode::Enter, KeyModifiers::CONTROL);
+        assert_eq!(
+            apply_key(ctrl_enter, &mut editor, InputOptions::default()),
+            KeyOutcome::Submit
+        );
+        assert_eq!(editor.text(), "a\n");
+    }
+
+    #[test]
+    fn test_submit_on_blank_line_option() {
+        let options = InputOptions {
+            submit_on_blank_line: true,
+            ..InputOptions::default()
+        };
+        let mut editor = LineEditor::new();
+        assert_eq!(
+            type_text(&mut editor, "one\ntwo\n \n", options),
+            KeyOutcome::Submit
+        );
+        assert_eq!(editor.text(), "one\ntwo");
+    }
+
+    #[test]
+    fn test_ctrl_c_aborts_and_ctrl_z_suspends() {
+        let mut editor = LineEditor::new();
+        type_text(&mut editor, "typed", InputOptions::default());
+
+        let ctrl_c = key(KeyCode::Char('c'), KeyModifiers::CONTROL);
+        assert_eq!(
+            apply_key(ctrl_c, &mut editor, InputOptions::default()),
+            KeyOutcome::Abort
+        );
+        let ctrl_z = key(KeyCode::Char('z'), KeyModifiers::CONTROL);
+        assert_eq!(
+            apply_key(ctrl_z, &mut editor, InputOptions::default()),
+            KeyOutcome::Suspend
+        );
+        // Neither changes the buffer
+        assert_eq!(editor.text(), "typed");
+    }
+
+    #[test]
+    fn test_visible_text() {
+        assert_eq!(visible_text("a\tbc\td"), "a   bc  d");
+        assert_eq!(visible_text("\t"), "    ");
+        assert_eq!(visible_text("crlf\r"), "crlf^M");
+        assert_eq!(visible_text("日\tx"), "日  x");
+        // Prompt " 1> " is four columns, then the tab fills four more
+        assert_eq!(cursor_column("\tx", 1, 1), 8);
+    }
+
+    #[test]
+    fn test_paste_is_verbatim() {
+        let yaml = "key:\n  - item\n\n\tnested: \"x\"\r\n";
+        assert_eq!(paste_text(yaml, InputOptions::default()), yaml);
+
+        let options = InputOptions {
+            normalize_line_endings: true,
+            ..InputOptions::default()
+        };
+        assert_eq!(paste_text("a\r\nb\rc\n", options), "a\nb\nc\n");
+    }
+
+    #[test]
+    fn test_multi_line_paste_splits_current_line() {
+        let mut editor = LineEditor::new();
+        type_text(&mut editor, "before after", InputOptions::default());
+        for _ in 0.."after".len() {
+            editor.move_left();
+        }
+        editor.insert_str(&paste_text("if x:\n    pass\n", InputOptions::default()));
+        assert_eq!(editor.lines(), ["before if x:", "    pass", "after"]);
+        assert_eq!(editor.cursor(), (2, 0));
+    }
+
+    #[test]
+    fn test_readline_bindings() {
+        let ctrl = |c| key(KeyCode::Char(c), KeyModifiers::CONTROL);
+        let alt = |c| key(KeyCode::Char(c), KeyModifiers::ALT);
+        let options = InputOptions::default();
+        let mut editor = LineEditor::new();
+        type_text(&mut editor, "git commit -m msg", options);
+
+        for event in [ctrl('a'), alt('f'), ctrl('f'), ctrl('k')] {
+            assert_eq!(apply_key(event, &mut editor, options), KeyOutcome::Edited);
+        }
+        assert_eq!(editor.text(), "git ");
+        apply_key(ctrl('e'), &mut editor, options);
+        apply_key(ctrl('y'), &mut editor, options);
+        assert_eq!(editor.text(), "git commit -m msg");
+
+        apply_key(ctrl('w'), &mut editor, options);
+        apply_key(alt('b'), &mut editor, options);
+        apply_key(alt('d'), &mut editor, options);
+        assert_eq!(editor.text(), "git commit - ");
+
+        // Unbound control keys do not type their letter
+        assert_eq!(
+            apply_key(ctrl('q'), &mut editor, options),
+            KeyOutcome::Ignored
+        );
+        assert_eq!(
+            apply_key(ctrl('l'), &mut editor, options),
+            KeyOutcome::ClearScreen
+        );
+        assert_eq!(editor.text(), "git commit - ");
+    }
+
+    #[test]
+    fn test_undo_and_redo_keys() {
+        let options = InputOptions::default();
+        let mut editor = LineEditor::new();
+        type_text(&mut editor, "echo ", options);
+        editor.insert_str("a mistaken paste");
+
+        // Ctrl+_ as most terminals send it
+        let undo = key(KeyCode::Char('7'), KeyModifiers::CONTROL);
+        assert_eq!(apply_key(undo, &mut editor, options), KeyOutcome::Edited);
+        assert_eq!(editor.text(), "echo ");
+
+        let redo = key(KeyCode::Char('/'), KeyModifiers::ALT);
+        apply_key(redo, &mut editor, options);
+        assert_eq!(editor.text(), "echo a mistaken paste");
     }
 }
@@ -0,0 +1,332 @@
+use std::{
+    env,
+    fs::{self, File, OpenOptions},
+    io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write},
+    path::PathBuf,
+    process,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{escaper, QuoteFormat};
+
+/// Escaped output is flushed to the writer whenever it grows past this size
+const OUTPUT_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Write `input` to `output` as a literal without holding more than a bounded
+/// buffer of it in memory. Raw literals need their delimiter decided before
+/// the first byte is written, so the input is read twice.
+pub fn stream_literal<R: BufRead + Seek, W: Write>(
+    input: &mut R,
+    output: &mut W,
+    quote_format: QuoteFormat,
+) -> io::Result<()> {
+    match quote_format {
+        QuoteFormat::Double | QuoteFormat::Single => {
+            stream_escaped_literal(input, output, quote_format)
+        }
+        QuoteFormat::Raw => stream_raw_literal(input, output),
+    }
+}
+
+/// Write a double- or single-quoted literal in a single pass over `input`.
+///
+/// Input that turns out not to be UTF-8 is an `InvalidData` error. Escaped
+/// output is held back until it reaches [`OUTPUT_BUFFER_SIZE`], so nothing is
+/// written for smaller inputs; for larger ones, whatever was flushed before
+/// the bad bytes were read is left in `output` as an unterminated literal,
+/// and the error says so.
+pub fn stream_escaped_literal<R: BufRead, W: Write>(
+    input: &mut R,
+    output: &mut W,
+    quote_format: QuoteFormat,
+) -> io::Result<()> {
+    let quote = match quote_format {
+        QuoteFormat::Double => b'"',
+        QuoteFormat::Single => b'\'',
+        QuoteFormat::Raw => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "raw literals need a seekable input",
+            ))
+        }
+    };
+
+    let table = escaper::escape_table(quote_format);
+    let mut validator = Utf8Validator::default();
+    let mut escaped = Vec::with_capacity(OUTPUT_BUFFER_SIZE);
+    let mut flushed = false;
+    escaped.push(quote);
+
+    loop {
+        let chunk = input.fill_buf()?;
+        if chunk.is_empty() {
+            break;
+        }
+        validator
+            .feed(chunk)
+            .map_err(|e| incomplete_output(e, flushed))?;
+
+        // Everything escaped is ASCII, so working on bytes never splits a character
+        escaper::escape_bytes_into(chunk, table, &mut escaped);
+        if escaped.len() >= OUTPUT_BUFFER_SIZE {
+            output.write_all(&escaped)?;
+            escaped.clear();
+            flushed = true;
+        }
+
+        let len = chunk.len();
+        input.consume(len);
+    }
+    validator
+        .finish()
+        .map_err(|e| incomplete_output(e, flushed))?;
+
+    escaped.push(quote);
+    output.write_all(&escaped)
+}
+
+/// Write a raw literal: one pass to find the delimiter, a second to copy the content
+pub fn stream_raw_literal<R: BufRead + Seek, W: Write>(
+    input: &mut R,
+    output: &mut W,
+) -> io::Result<()> {
+    let start = input.stream_position()?;
+    let delimiter = scan_raw_delimiter(input)?;
+    input.seek(SeekFrom::Start(start))?;
+
+    write!(output, "r{delimiter}\"")?;
+    io::copy(input, output)?;
+    write!(output, "\"{delimiter}")
+}
+
+/// Streaming counterpart of [`crate::find_raw_string_delimiter`]
+fn scan_raw_delimiter<R: BufRead>(input: &mut R) -> io::Result<String> {
+    let mut validator = Utf8Validator::default();
+    let mut max_consecutive_quotes = 0;
+    let mut current_quotes = 0;
+
+    loop {
+        let chunk = input.fill_buf()?;
+        if chunk.is_empty() {
+            break;
+        }
+        validator.feed(chunk)?;
+
+        for &byte in chunk {
+            if byte == b'"' {
+                current_quotes += 1;
+                max_consecutive_quotes = max_consecutive_quotes.max(current_quotes);
+            } else {
+                current_quotes = 0;
+            }
+        }
+
+        let len = chunk.len();
+        input.consume(len);
+    }
+    validator.finish()?;
+
+    Ok("#".repeat(max_consecutive_quotes + 1))
+}
+
+/// Checks that a byte stream is UTF-8, allowing characters to be split across chunks
+#[derive(Debug, Default)]
+struct Utf8Validator {
+    /// Start of a character whose remaining bytes are in the next chunk
+    pending: Vec<u8>,
+}
+
+impl Utf8Validator {
+    fn feed(&mut self, mut chunk: &[u8]) -> io::Result<()> {
+        if let Some(&first) = self.pending.first() {
+            let width = match first {
+                0xe0..=0xef => 3,
+                0xf0..=0xf7 => 4,
+                _ => 2,
+            };
+            let take = (width - self.pending.len()).min(chunk.len());
+            self.pending.extend_from_slice(&chunk[..take]);
+            chunk = &chunk[take..];
+            if self.pending.len() < width {
+                return Ok(());
+            }
+            std::str::from_utf8(&self.pending).map_err(|_| invalid_utf8())?;
+            self.pending.clear();
+        }
+
+        match std::str::from_utf8(chunk) {
+            Ok(_) => Ok(()),
+            // An incomplete character at the very end may be finished by the next chunk
+            Err(e) if e.error_len().is_none() => {
+                self.pending.extend_from_slice(&chunk[e.valid_up_to()..]);
+                Ok(())
+            }
+            Err(_) => Err(invalid_utf8()),
+        }
+    }
+
+    fn finish(&self) -> io::Result<()> {
+        if self.pending.is_empty() {
+            Ok(())
+        } else {
+            Err(invalid_utf8())
+        }
+    }
+}
+
+fn invalid_utf8() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        "stream did not contain valid UTF-8",
+    )
+}
+
+/// Add to a validation error that part of the literal was already written
+fn incomplete_output(error: io::Error, flushed: bool) -> io::Error {
+    if flushed {
+        io::Error::new(
+            error.kind(),
+            format!("{error}; the literal written before it was found is incomplete"),
+        )
+    } else {
+        error
+    }
+}
+
+/// A non-seekable input copied to a temporary file so it can be read twice.
+/// The file may hold secrets piped in, so on Unix only the owner can read it.
+pub struct Spool {
+    reader: Option<BufReader<File>>,
+    path: PathBuf,
+}
+
+impl Spool {
+    pub fn new<R: Read>(input: &mut R) -> io::Result<Self> {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.subsec_nanos());
+        let path = env::temp_dir().join(format!("quot-{}-{nanos}.spool", process::id()));
+        let mut options = OpenOptions::new();
+        // `create_new` refuses to follow a file or symlink planted at the path
+        options.read(true).write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut file = options.open(&path)?;
+        let mut spool = Spool { reader: None, path };
+
+        io::copy(input, &mut file)?;
+        file.seek(SeekFrom::Start(0))?;
+        spool.reader = Some(BufReader::new(file));
+        Ok(spool)
+    }
+
+    pub fn reader(&mut self) -> &mut BufReader<File> {
+        self.reader.as_mut().expect("spool is open until dropped")
+    }
+}
+
+impl Drop for Spool {
+    fn drop(&mut self) {
+        // Close the file first; Windows refuses to delete open files
+        self.reader = None;
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format_literal;
+    use std::io::Cursor;
+
+    fn stream(input: &str, quote_format: QuoteFormat, capacity: usize) -> String {
+        let mut reader = BufReader::with_capacity(capacity, Cursor::new(input.as_bytes()));
+        let mut output = Vec::new();
+        stream_literal(&mut reader, &mut output, quote_format).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_matches_format_literal() {
+        let inputs = [
+            "",
+            "plain",
+            "Hello \"world\"\nTab:\tEnd\r\n",
+            "it's a \\ backslash",
+            "quotes \"\"\" and \"#",
+            "日本語 ünïcödé 🦀",
+        ];
+        for input in inputs {
+            for quote_format in [QuoteFormat::Double, QuoteFormat::Single, QuoteFormat::Raw] {
+                // A one-byte buffer splits every character and escape across chunks
+                for capacity in [1, 3, 8192] {
+                    assert_eq!(
+                        stream(input, quote_format, capacity),
+                        format_literal(input, quote_format),
+                        "input {input:?} with {quote_format:?} and capacity {capacity}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_large_input_is_flushed_in_pieces() {
+        let input = "\"".repeat(OUTPUT_BUFFER_SIZE * 3);
+        let output = stream(&input, QuoteFormat::Double, 8192);
+        assert_eq!(output.len(), input.len() * 2 + 2);
+    }
+
+    #[test]
+    fn test_invalid_utf8_is_rejected() {
+        for bytes in [&b"ab\xffcd"[..], b"truncated \xe6\x97"] {
+            let mut reader = BufReader::with_capacity(2, Cursor::new(bytes));
+            let error = stream_literal(&mut reader, &mut Vec::new(), QuoteFormat::Double);
+            assert_eq!(error.unwrap_err().kind(), io::ErrorKind::InvalidData);
+        }
+    }
+
+    #[test]
+    fn test_invalid_utf8_after_a_flush_leaves_partial_output() {
+        // Small inputs are validated in full before anything is written
+        let mut output = Vec::new();
+        let mut reader = Cursor::new(&b"ab\xffcd"[..]);
+        assert!(stream_escaped_literal(&mut reader, &mut output, QuoteFormat::Double).is_err());
+        assert!(output.is_empty());
+
+        // Larger ones have been partly written by the time the bad byte is read
+        let mut bytes = vec![b'a'; OUTPUT_BUFFER_SIZE * 2];
+        bytes.push(0xff);
+        let mut reader = BufReader::with_capacity(8192, Cursor::new(bytes));
+        let mut output = Vec::new();
+        let error =
+            stream_escaped_literal(&mut reader, &mut output, QuoteFormat::Double).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert!(error.to_string().ends_with("is incomplete"));
+        assert!(output.len() >= OUTPUT_BUFFER_SIZE);
+        assert!(output.starts_with(b"\"aaa") && output.ends_with(b"aaa"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_spool_is_private() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let spool = Spool::new(&mut Cursor::new("secret")).unwrap();
+        let mode = fs::metadata(&spool.path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    #[test]
+    fn test_raw_literal_from_spooled_input() {
+        let mut spool = Spool::new(&mut Cursor::new("say \"\"hi\"\"")).unwrap();
+        let path = spool.path.clone();
+
+        let mut output = Vec::new();
+        stream_literal(spool.reader(), &mut output, QuoteFormat::Raw).unwrap();
+        assert_eq!(output, b"r###\"say \"\"hi\"\"\"###");
+
+        drop(spool);
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,230 @@
+use std::{
+    fs::OpenOptions,
+    io::{self, Write},
+    panic,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Once,
+    },
+};
+
+use atty::Stream;
+use crossterm::{
+    cursor,
+    event::{
+        DisableBracketedPaste, EnableBracketedPaste, KeyboardEnhancementFlags,
+        PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+    },
+    queue, terminal,
+};
+
+/// Raw mode is on and has not been undone yet
+static RAW_MODE: AtomicBool = AtomicBool::new(false);
+/// Keyboard enhancement flags were pushed and not popped yet
+static KEYBOARD_ENHANCEMENT: AtomicBool = AtomicBool::new(false);
+/// Guards alive right now; only the outermost one changes the terminal
+static DEPTH: AtomicUsize = AtomicUsize::new(0);
+static INSTALL_HOOKS: Once = Once::new();
+
+/// Puts the terminal in raw mode for as long as it lives. However quot stops
+/// — returning normally, an error propagating with `?`, a panic, or SIGTERM
+/// or SIGHUP — the terminal is left in cooked mode with the cursor visible.
+/// Guards nest: one entered while another is alive leaves the terminal as it
+/// is, and dropping it leaves raw mode on for the outer one.
+pub struct TerminalGuard {
+    keyboard_enhancement: bool,
+    bracketed_paste: bool,
+}
+
+impl TerminalGuard {
+    pub fn enter_raw_mode() -> io::Result<Self> {
+        if DEPTH.fetch_add(1, Ordering::SeqCst) == 0 {
+            INSTALL_HOOKS.call_once(install_hooks);
+            if let Err(error) = enable_raw_mode() {
+                DEPTH.fetch_sub(1, Ordering::SeqCst);
+                return Err(error);
+            }
+        }
+        Ok(TerminalGuard {
+            keyboard_enhancement: false,
+            bracketed_paste: false,
+        })
+    }
+
+    /// Have the terminal report modified keys such as Ctrl+Enter unambiguously,
+    /// if it supports this. Returns whether it does.
+    pub fn enable_keyboard_enhancement(&mut self) -> io::Result<bool> {
+        // crossterm sends its support query to stdout, which may be a file
+        // capturing our output; only ask when stdout is the terminal
+        self.keyboard_enhancement = atty::is(Stream::Stdout)
+            && matches!(terminal::supports_keyboard_enhancement(), Ok(true));
+        if self.keyboard_enhancement {
+            push_keyboard_enhancement()?;
+        }
+        Ok(self.keyboard_enhancement)
+    }
+
+    /// Have the terminal deliver pastes as a single event instead of as typed
+    /// keys, so pasted newlines don't act like Enter
+    pub fn enable_bracketed_paste(&mut self) -> io::Result<()> {
+        self.bracketed_paste = true;
+        let mut output = terminal_output();
+        queue!(output, EnableBracketedPaste)?;
+        output.flush()
+    }
+
+    /// Stop the process as the terminal's suspend key would, with the terminal
+    /// restored while stopped, and take it back once the shell resumes it
+    pub fn suspend(&mut self) -> io::Result<()> {
+        restore();
+        raise_suspend()?;
+
+        enable_raw_mode()?;
+        if self.keyboard_enhancement {
+            push_keyboard_enhancement()?;
+        }
+        if self.bracketed_paste {
+            self.enable_bracketed_paste()?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        if DEPTH.fetch_sub(1, Ordering::SeqCst) == 1 {
+            restore();
+        }
+    }
+}
+
+fn enable_raw_mode() -> io::Result<()> {
+    terminal::enable_raw_mode()?;
+    RAW_MODE.store(true, Ordering::SeqCst);
+    Ok(())
+}
+
+/// The controlling terminal, for prompts and control sequences that must
+/// never end up in quot's output. Falls back to stderr where there is no
+/// `/dev/tty`, such as on Windows.
+pub fn terminal_output() -> Box<dyn Write> {
+    match OpenOptions::new().write(true).open("/dev/tty") {
+        Ok(tty) => Box::new(tty),
+        Err(_) => Box::new(io::stderr()),
+    }
+}
+
+fn push_keyboard_enhancement() -> io::Result<()> {
+    let mut output = terminal_output();
+    queue!(
+        output,
+        PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES)
+    )?;
+    output.flush()?;
+    KEYBOARD_ENHANCEMENT.store(true, Ordering::SeqCst);
+    Ok(())
+}
+
+/// Undo everything a guard may have changed. Safe to call more than once and
+/// from any thread; only the first call after entering raw mode does anything.
+pub fn restore() {
+    if !RAW_MODE.swap(false, Ordering::SeqCst) {
+        return;
+    }
+    // Errors are ignored: this runs while already failing, and each step
+    // should still be attempted if an earlier one fails
+    let mut output = terminal_output();
+    if KEYBOARD_ENHANCEMENT.swap(false, Ordering::SeqCst) {
+        let _ = queue!(output, PopKeyboardEnhancementFlags);
+    }
+    let _ = queue!(output, DisableBracketedPaste, cursor::Show);
+    let _ = output.flush();
+    let _ = terminal::disable_raw_mode();
+}
+
+fn install_hooks() {
+    // Restore before the panic message is printed, so it is readable
+    let previous_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        restore();
+        previous_hook(info);
+    }));
+
+    #[cfg(unix)]
+    install_signal_handlers();
+}
+
+/// Restore the terminal when the process is asked to stop, then stop the way
+/// the signal would have stopped it. Signals are handled on their own thread,
+/// where restoring is not limited to async-signal-safe calls.
+#[cfg(unix)]
+fn install_signal_handlers() {
+    use signal_hook::{
+        consts::{SIGHUP, SIGTERM},
+        iterator::Signals,
+        low_level,
+    };
+
+    let Ok(mut signals) = Signals::new([SIGTERM, SIGHUP]) else {
+        return;
+    };
+    std::thread::spawn(move || {
+        if let Some(signal) = signals.forever().next() {
+            restore();
+            let _ = low_level::emulate_default_handler(signal);
+        }
+    });
+}
+
+/// Raw mode keeps the terminal from sending SIGTSTP itself, so it is raised here
+#[cfg(unix)]
+fn raise_suspend() -> io::Result<()> {
+    signal_hook::low_level::raise(signal_hook::consts::SIGTSTP)
+}
+
+/// Windows consoles have no job control, so there is nothing to suspend to
+#[cfg(not(unix))]
+fn raise_suspend() -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Mutex, MutexGuard, PoisonError};
+
+    use super::*;
+
+    /// The tests share the process-wide terminal state
+    fn lock() -> MutexGuard<'static, ()> {
+        static LOCK: Mutex<()> = Mutex::new(());
+        LOCK.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    #[test]
+    fn test_restore_without_raw_mode_is_a_no_op() {
+        let _lock = lock();
+        // Must not touch a terminal that quot never changed
+        restore();
+        restore();
+        assert!(!RAW_MODE.load(Ordering::SeqCst));
+        assert!(!KEYBOARD_ENHANCEMENT.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_dropping_a_nested_guard_keeps_raw_mode() {
+        let _lock = lock();
+        // Stand in for an outer guard that has already turned raw mode on,
+        // so the test never touches a real terminal
+        RAW_MODE.store(true, Ordering::SeqCst);
+        DEPTH.store(1, Ordering::SeqCst);
+
+        let nested = TerminalGuard::enter_raw_mode().unwrap();
+        assert_eq!(DEPTH.load(Ordering::SeqCst), 2);
+        drop(nested);
+        assert!(RAW_MODE.load(Ordering::SeqCst));
+        assert_eq!(DEPTH.load(Ordering::SeqCst), 1);
+
+        RAW_MODE.store(false, Ordering::SeqCst);
+        DEPTH.store(0, Ordering::SeqCst);
+    }
+}
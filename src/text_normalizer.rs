@@ -0,0 +1,262 @@
+/// Elements whose start and end begin a new line of text
+const BLOCK_ELEMENTS: &[&str] = &[
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "dd",
+    "div",
+    "dl",
+    "dt",
+    "figcaption",
+    "figure",
+    "footer",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "hr",
+    "li",
+    "main",
+    "nav",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "table",
+    "tr",
+    "ul",
+];
+
+/// Elements whose contents are never rendered as text
+const HIDDEN_ELEMENTS: &[&str] = &["head", "script", "style", "template", "title"];
+
+/// Convert an HTML fragment, such as the text/html clipboard flavour, into the
+/// plain text a browser would show for it
+pub fn strip_html(html: &str) -> String {
+    let mut text = String::new();
+    let mut rest = html;
+    let mut pre_depth = 0usize;
+
+    while let Some(start) = rest.find('<') {
+        push_text(&mut text, &rest[..start], pre_depth > 0);
+        rest = &rest[start..];
+
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+            continue;
+        }
+
+        let Some(end) = rest.find('>') else {
+            // A stray '<' that never closes is just text
+            push_text(&mut text, rest, pre_depth > 0);
+            rest = "";
+            break;
+        };
+        let tag = &rest[1..end];
+        rest = &rest[end + 1..];
+
+        let closing = tag.starts_with('/');
+        let name = tag_name(tag);
+
+        if !closing && HIDDEN_ELEMENTS.contains(&name.as_str()) {
+            // Skip everything up to the matching end tag
+            let end_tag = format!("</{name}");
+            rest = find_ignore_case(rest, &end_tag)
+                .and_then(|position| {
+                    let after = &rest[position..];
+                    after.find('>').map(|end| &after[end + 1..])
+                })
+                .unwrap_or("");
+            continue;
+        }
+
+        if name == "br" {
+            text.push('\n');
+        } else if BLOCK_ELEMENTS.contains(&name.as_str()) {
+            start_new_line(&mut text);
+            if name == "pre" {
+                pre_depth = if closing {
+                    pre_depth.saturating_sub(1)
+                } else {
+                    pre_depth + 1
+                };
+            }
+        } else if matches!(name.as_str(), "td" | "th") && !closing {
+            // Separate table cells the way browsers do when copying as text
+            if !text.is_empty() && !text.ends_with('\n') {
+                text.push('\t');
+            }
+        }
+    }
+    push_text(&mut text, rest, pre_depth > 0);
+
+    let lines: Vec<&str> = text.lines().map(str::trim_end).collect();
+    lines.join("\n").trim_matches('\n').to_string()
+}
+
+/// Replace typographic characters picked up from rendered documents with
+/// their plain ASCII equivalents: non-breaking and other fixed-width spaces
+/// become spaces, and curly quotes become straight ones
+pub fn normalize_typography(text: &str) -> String {
+    text.chars()
+        .filter_map(|c| match c {
+            '\u{a0}' | '\u{2007}' | '\u{202f}' | '\u{2000}'..='\u{200a}' => Some(' '),
+            '\u{2018}' | '\u{2019}' | '\u{201a}' | '\u{201b}' | '\u{2032}' => Some('\''),
+            '\u{201c}' | '\u{201d}' | '\u{201e}' | '\u{201f}' | '\u{2033}' => Some('"'),
+            // Zero-width spaces and byte order marks are invisible, so drop them
+            '\u{200b}' | '\u{feff}' => None,
+            _ => Some(c),
+        })
+        .collect()
+}
+
+/// Lowercased element name of a tag's contents, e.g. `p` for `/P class="x"`
+fn tag_name(tag: &str) -> String {
+    tag.trim_start_matches('/')
+        .chars()
+        .take_while(char::is_ascii_alphanumeric)
+        .collect::<String>()
+        .to_ascii_lowercase()
+}
+
+fn find_ignore_case(haystack: &str, needle: &str) -> Option<usize> {
+    haystack
+        .to_ascii_lowercase()
+        .find(&needle.to_ascii_lowercase())
+}
+
+fn start_new_line(text: &mut String) {
+    if !text.is_empty() && !text.ends_with('\n') {
+        text.push('\n');
+    }
+}
+
+/// Append a run of text between tags, collapsing whitespace outside `<pre>`
+fn push_text(text: &mut String, raw: &str, preformatted: bool) {
+    let decoded = decode_entities(raw);
+    if preformatted {
+        text.push_str(&decoded);
+        return;
+    }
+
+    for c in decoded.chars() {
+        // Only ASCII whitespace collapses; an entity-encoded NBSP stays as it is
+        if c.is_ascii_whitespace() {
+            if !text.is_empty() && !text.ends_with([' ', '\n', '\t']) {
+                text.push(' ');
+            }
+        } else {
+            text.push(c);
+        }
+    }
+}
+
+/// Decode character references such as `&amp;`, `&#39;` and `&#x2019;`
+fn decode_entities(raw: &str) -> String {
+    let mut decoded = String::with_capacity(raw.len());
+    let mut rest = raw;
+
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let reference = rest[1..]
+            .find(';')
+            .map(|end| &rest[1..=end])
+            .and_then(|body| Some((decode_entity(body)?, body.len() + 2)));
+        match reference {
+            Some((c, len)) => {
+                decoded.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+fn decode_entity(body: &str) -> Option<char> {
+    if let Some(number) = body.strip_prefix('#') {
+        let code = match number.strip_prefix(['x', 'X']) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+            None => number.parse().ok()?,
+        };
+        return char::from_u32(code);
+    }
+
+    let c = match body {
+        "amp" => '&',
+        "lt" => '<',
+        "gt" => '>',
+        "quot" => '"',
+        "apos" => '\'',
+        "nbsp" => '\u{a0}',
+        "lsquo" => '\u{2018}',
+        "rsquo" => '\u{2019}',
+        "ldquo" => '\u{201c}',
+        "rdquo" => '\u{201d}',
+        "ndash" => '\u{2013}',
+        "mdash" => '\u{2014}',
+        "hellip" => '\u{2026}',
+        "copy" => '\u{a9}',
+        _ => return None,
+    };
+    Some(c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_html_blocks_and_inline() {
+        let html = "<meta charset=\"utf-8\"><p>Hello <b>bold</b>\n  world</p><p>Second<br>line</p>";
+        assert_eq!(strip_html(html), "Hello bold world\nSecond\nline");
+    }
+
+    #[test]
+    fn test_strip_html_decodes_entities() {
+        let html = "<span>a &lt; b &amp;&amp; c &#39;d&#x27; &unknown; &</span>";
+        assert_eq!(strip_html(html), "a < b && c 'd' &unknown; &");
+    }
+
+    #[test]
+    fn test_strip_html_preserves_pre() {
+        let html = "<pre><code>fn main() {\n    let x = 1;\n}</code></pre>";
+        assert_eq!(strip_html(html), "fn main() {\n    let x = 1;\n}");
+    }
+
+    #[test]
+    fn test_strip_html_skips_hidden_elements_and_comments() {
+        let html = "<style>p { color: red }</style><!--StartFragment-->text<!--EndFragment--><SCRIPT>x()</SCRIPT>";
+        assert_eq!(strip_html(html), "text");
+    }
+
+    #[test]
+    fn test_strip_html_table_cells() {
+        let html = "<table><tr><td>a</td><td>b</td></tr><tr><td>c</td><td>d</td></tr></table>";
+        assert_eq!(strip_html(html), "a\tb\nc\td");
+    }
+
+    #[test]
+    fn test_strip_html_keeps_nbsp() {
+        // Normalising is a separate step, so the NBSP survives stripping
+        assert_eq!(strip_html("a&nbsp;b"), "a\u{a0}b");
+    }
+
+    #[test]
+    fn test_normalize_typography() {
+        let text = "\u{201c}Don\u{2019}t\u{201d}\u{a0}say\u{202f}\u{2018}hi\u{2019}\u{200b}";
+        assert_eq!(normalize_typography(text), "\"Don't\" say 'hi'");
+        // Dashes and other characters are left alone
+        assert_eq!(normalize_typography("a\u{2014}b"), "a\u{2014}b");
+    }
+}
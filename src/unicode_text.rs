@@ -0,0 +1,108 @@
+//! Grapheme cluster segmentation and terminal column widths for the line editor,
+//! built on `unicode-segmentation` (UAX #29) and `unicode-width` (UAX #11).
+
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// The grapheme clusters of `text`
+pub fn graphemes(text: &str) -> impl Iterator<Item = &str> {
+    text.graphemes(true)
+}
+
+/// Number of grapheme clusters in `text`
+pub fn grapheme_count(text: &str) -> usize {
+    graphemes(text).count()
+}
+
+/// Byte offset of the start of the `index`th grapheme cluster, or the end of
+/// `text` if there are fewer clusters. Only the clusters before `index` are
+/// scanned, so looking up the cursor costs nothing past it.
+pub fn byte_offset(text: &str, index: usize) -> usize {
+    text.grapheme_indices(true)
+        .nth(index)
+        .map_or(text.len(), |(offset, _)| offset)
+}
+
+/// Columns a grapheme cluster takes up in a terminal
+pub fn grapheme_width(grapheme: &str) -> usize {
+    if grapheme.starts_with(char::is_control) {
+        0
+    } else {
+        grapheme.width()
+    }
+}
+
+/// Columns `text` takes up in a terminal
+pub fn display_width(text: &str) -> usize {
+    graphemes(text).map(grapheme_width).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clusters(text: &str) -> Vec<&str> {
+        graphemes(text).collect()
+    }
+
+    #[test]
+    fn test_ascii_and_crlf() {
+        assert_eq!(clusters("ab\r\nc"), ["a", "b", "\r\n", "c"]);
+        assert_eq!(grapheme_count(""), 0);
+        assert_eq!(byte_offset("", 3), 0);
+    }
+
+    #[test]
+    fn test_combining_marks_join_their_base() {
+        // "é" written as "e" + COMBINING ACUTE ACCENT
+        let text = "ce\u{301}t";
+        assert_eq!(clusters(text), ["c", "e\u{301}", "t"]);
+        assert_eq!(display_width(text), 3);
+        assert_eq!(byte_offset(text, 2), 4);
+    }
+
+    #[test]
+    fn test_east_asian_width() {
+        assert_eq!(display_width("日本語"), 6);
+        assert_eq!(display_width("ｱｲｳ"), 3); // halfwidth katakana
+        assert_eq!(display_width("ＡＢ"), 4); // fullwidth latin
+        assert_eq!(display_width("한국어"), 6);
+        assert_eq!(display_width("é"), 1);
+    }
+
+    #[test]
+    fn test_hangul_jamo_compose() {
+        // ᄒ + ᅡ + ᆫ renders as the single syllable 한
+        let text = "\u{1112}\u{1161}\u{11ab}";
+        assert_eq!(clusters(text), [text]);
+        assert_eq!(display_width(text), 2);
+    }
+
+    #[test]
+    fn test_emoji_sequences() {
+        let family = "👨\u{200d}👩\u{200d}👧";
+        assert_eq!(clusters(family), [family]);
+        assert_eq!(display_width(family), 2);
+
+        let thumbs = "👍\u{1f3fd}";
+        assert_eq!(clusters(thumbs), [thumbs]);
+        assert_eq!(display_width(thumbs), 2);
+
+        // Text-style heart turned into an emoji by VS16
+        assert_eq!(display_width("\u{2764}\u{fe0f}"), 2);
+        assert_eq!(display_width("🦀x"), 3);
+    }
+
+    #[test]
+    fn test_flags_pair_regional_indicators() {
+        let flags = "🇯🇵🇫🇷";
+        assert_eq!(clusters(flags), ["🇯🇵", "🇫🇷"]);
+        assert_eq!(display_width(flags), 4);
+    }
+
+    #[test]
+    fn test_invisible_characters() {
+        assert_eq!(display_width("a\u{200b}b"), 2);
+        assert_eq!(display_width("\u{feff}"), 0);
+    }
+}
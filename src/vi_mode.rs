@@ -0,0 +1,845 @@
+use std::{env, fs, path::PathBuf};
+
+use clap::ValueEnum;
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+
+use crate::{
+    line_editor::{LineEditor, Position},
+    stdin_handler::{self, InputOptions, KeyOutcome},
+    unicode_text,
+};
+
+/// Environment variable choosing the editing mode when `--editing-mode` isn't given
+pub const EDITING_MODE_VARIABLE: &str = "QUOT_EDITING_MODE";
+
+/// Key bindings for interactive input
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum EditingMode {
+    /// Readline's default Emacs-style bindings (default)
+    #[default]
+    Emacs,
+    /// vi-style insert and normal modes
+    Vi,
+}
+
+impl EditingMode {
+    /// The mode to use when none is given on the command line: from
+    /// `QUOT_EDITING_MODE`, then from the readline configuration, as a shell
+    /// using readline would pick it
+    pub fn from_environment() -> Self {
+        if let Some(mode) = env::var(EDITING_MODE_VARIABLE)
+            .ok()
+            .and_then(|value| EditingMode::from_str(value.trim(), true).ok())
+        {
+            return mode;
+        }
+        inputrc_path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|contents| parse_inputrc(&contents))
+            .unwrap_or_default()
+    }
+}
+
+/// `$INPUTRC`, or `~/.inputrc` as readline falls back to
+fn inputrc_path() -> Option<PathBuf> {
+    match env::var_os("INPUTRC") {
+        Some(path) => Some(PathBuf::from(path)),
+        None => env::var_os("HOME").map(|home| PathBuf::from(home).join(".inputrc")),
+    }
+}
+
+/// The editing mode an inputrc selects, written either the readline way
+/// (`set editing-mode vi`) or the shell way (`set -o vi`). The last one wins.
+pub fn parse_inputrc(contents: &str) -> Option<EditingMode> {
+    let mut mode = None;
+    for line in contents.lines() {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let (["set", "editing-mode", value] | ["set", "-o", value]) = words.as_slice() else {
+            continue;
+        };
+        if let Ok(value) = EditingMode::from_str(value, true) {
+            mode = Some(value);
+        }
+    }
+    mode
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum Mode {
+    /// Keys type text, as in Emacs mode; Esc switches to normal mode
+    #[default]
+    Insert,
+    /// Keys are commands
+    Normal,
+}
+
+/// Where the cursor may move in normal mode, alone or after an operator
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Motion {
+    Left,
+    Right,
+    Up,
+    Down,
+    WordForward,
+    WordBackward,
+    WordEnd,
+    LineStart,
+    LineEnd,
+    FirstLine,
+    LastLine,
+}
+
+impl Motion {
+    fn from_keys(keys: &str) -> Parse<Motion> {
+        let motion = match keys {
+            "" | "g" => return Parse::Incomplete,
+            "h" => Motion::Left,
+            "l" | " " => Motion::Right,
+            "k" => Motion::Up,
+            "j" => Motion::Down,
+            "w" => Motion::WordForward,
+            "b" => Motion::WordBackward,
+            "e" => Motion::WordEnd,
+            "0" => Motion::LineStart,
+            "$" => Motion::LineEnd,
+            "gg" => Motion::FirstLine,
+            "G" => Motion::LastLine,
+            _ => return Parse::Invalid,
+        };
+        Parse::Complete(motion)
+    }
+
+    /// Operators act on whole lines for motions between lines
+    fn is_linewise(self) -> bool {
+        matches!(
+            self,
+            Motion::Up | Motion::Down | Motion::FirstLine | Motion::LastLine
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operator {
+    Delete,
+    Change,
+    Yank,
+}
+
+impl Operator {
+    fn from_key(key: char) -> Option<Operator> {
+        match key {
+            'd' => Some(Operator::Delete),
+            'c' => Some(Operator::Change),
+            'y' => Some(Operator::Yank),
+            _ => None,
+        }
+    }
+}
+
+/// What an operator acts on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Target {
+    Motion(Motion),
+    /// The current line, when the operator key is doubled (`dd`, `cc`, `yy`)
+    Line,
+}
+
+/// Where insert mode starts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Entry {
+    BeforeCursor,
+    AfterCursor,
+    LineStart,
+    LineEnd,
+    LineBelow,
+    LineAbove,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Command {
+    Move(Motion),
+    Operate(Operator, Target),
+    Insert(Entry),
+    Put { before: bool },
+    Undo,
+    Repeat,
+}
+
+/// The normal mode keys typed so far, read as a command
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Parse<T> {
+    Complete(T),
+    /// A prefix of a command, such as `d` or `g`
+    Incomplete,
+    Invalid,
+}
+
+impl<T> Parse<T> {
+    fn map<U>(self, f: impl FnOnce(T) -> U) -> Parse<U> {
+        match self {
+            Parse::Complete(value) => Parse::Complete(f(value)),
+            Parse::Incomplete => Parse::Incomplete,
+            Parse::Invalid => Parse::Invalid,
+        }
+    }
+}
+
+fn parse_command(keys: &str) -> Parse<Command> {
+    let command = match keys {
+        "i" => Command::Insert(Entry::BeforeCursor),
+        "a" => Command::Insert(Entry::AfterCursor),
+        "I" => Command::Insert(Entry::LineStart),
+        "A" => Command::Insert(Entry::LineEnd),
+        "o" => Command::Insert(Entry::LineBelow),
+        "O" => Command::Insert(Entry::LineAbove),
+        "x" => Command::Operate(Operator::Delete, Target::Motion(Motion::Right)),
+        "X" => Command::Operate(Operator::Delete, Target::Motion(Motion::Left)),
+        "D" => Command::Operate(Operator::Delete, Target::Motion(Motion::LineEnd)),
+        "C" => Command::Operate(Operator::Change, Target::Motion(Motion::LineEnd)),
+        "p" => Command::Put { before: false },
+        "P" => Command::Put { before: true },
+        "u" => Command::Undo,
+        "." => Command::Repeat,
+        "dd" => Command::Operate(Operator::Delete, Target::Line),
+        "cc" => Command::Operate(Operator::Change, Target::Line),
+        "yy" => Command::Operate(Operator::Yank, Target::Line),
+        _ => {
+            let mut chars = keys.chars();
+            return match chars.next().and_then(Operator::from_key) {
+                Some(operator) => Motion::from_keys(chars.as_str())
+                    .map(|motion| Command::Operate(operator, Target::Motion(motion))),
+                None => Motion::from_keys(keys).map(Command::Move),
+            };
+        }
+    };
+    Parse::Complete(command)
+}
+
+/// Text deleted or yanked by an operator, for `p` and `P`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Register {
+    text: String,
+    /// Whole lines, which are put on lines of their own
+    linewise: bool,
+}
+
+/// Which class of text a grapheme belongs to for `w`, `b` and `e`: words are
+/// runs of letters, digits and underscores, or runs of other non-blanks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Class {
+    Blank,
+    Word,
+    Punctuation,
+}
+
+fn class(grapheme: &str) -> Class {
+    match grapheme.chars().next() {
+        None => Class::Blank,
+        Some(c) if c.is_whitespace() => Class::Blank,
+        Some(c) if c.is_alphanumeric() || c == '_' => Class::Word,
+        Some(_) => Class::Punctuation,
+    }
+}
+
+/// The buffer split into graphemes, for walking word motions across lines.
+/// The position at the end of a line stands for its line break, which is blank.
+struct Graphemes<'a> {
+    lines: Vec<Vec<&'a str>>,
+}
+
+impl<'a> Graphemes<'a> {
+    fn new(lines: &'a [String]) -> Self {
+        Graphemes {
+            lines: lines
+                .iter()
+                .map(|line| unicode_text::graphemes(line).collect())
+                .collect(),
+        }
+    }
+
+    fn class(&self, (row, column): Position) -> Class {
+        self.lines[row]
+            .get(column)
+            .map_or(Class::Blank, |grapheme| class(grapheme))
+    }
+
+    fn next(&self, (row, column): Position) -> Option<Position> {
+        if column < self.lines[row].len() {
+            Some((row, column + 1))
+        } else if row + 1 < self.lines.len() {
+            Some((row + 1, 0))
+        } else {
+            None
+        }
+    }
+
+    fn previous(&self, (row, column): Position) -> Option<Position> {
+        if column > 0 {
+            Some((row, column - 1))
+        } else if row > 0 {
+            Some((row - 1, self.lines[row - 1].len()))
+        } else {
+            None
+        }
+    }
+
+    /// Start of the next word, or an empty line, which vi also stops at
+    fn word_forward(&self, start: Position) -> Position {
+        let mut position = start;
+        let start_class = self.class(start);
+        while start_class != Class::Blank && self.class(position) == start_class {
+            match self.next(position) {
+                Some(next) => position = next,
+                None => return position,
+            }
+        }
+        while self.class(position) == Class::Blank {
+            if position.0 != start.0 && self.lines[position.0].is_empty() {
+                break;
+            }
+            match self.next(position) {
+                Some(next) => position = next,
+                None => return position,
+            }
+        }
+        position
+    }
+
+    /// Start of the word before `start`, or of the one it is in
+    fn word_backward(&self, start: Position) -> Position {
+        let Some(mut position) = self.previous(start) else {
+            return start;
+        };
+        while self.class(position) == Class::Blank {
+            match self.previous(position) {
+                Some(previous) => position = previous,
+                None => return position,
+            }
+        }
+        let word_class = self.class(position);
+        while let Some(previous) = self.previous(position) {
+            if self.class(previous) != word_class {
+                break;
+            }
+            position = previous;
+        }
+        position
+    }
+
+    /// Last grapheme of the word after `start`, or of the one it is in
+    fn word_end(&self, start: Position) -> Position {
+        let Some(mut position) = self.next(start) else {
+            return start;
+        };
+        while self.class(position) == Class::Blank {
+            match self.next(position) {
+                Some(next) => position = next,
+                None => return position,
+            }
+        }
+        let word_class = self.class(position);
+        while let Some(next) = self.next(position) {
+            if self.class(next) != word_class {
+                break;
+            }
+            position = next;
+        }
+        position
+    }
+}
+
+fn line_len(editor: &LineEditor, row: usize) -> usize {
+    unicode_text::grapheme_count(&editor.lines()[row])
+}
+
+/// Where `motion` takes the cursor; columns may be one past the last grapheme
+/// so that operators reach the end of the line
+fn motion_target(motion: Motion, editor: &LineEditor) -> Position {
+    let (row, column) = editor.cursor();
+    let last_row = editor.lines().len() - 1;
+    match motion {
+        Motion::Left => (row, column.saturating_sub(1)),
+        Motion::Right => (row, (column + 1).min(line_len(editor, row))),
+        Motion::Up => (row.saturating_sub(1), column),
+        Motion::Down => ((row + 1).min(last_row), column),
+        Motion::WordForward => Graphemes::new(editor.lines()).word_forward((row, column)),
+        Motion::WordBackward => Graphemes::new(editor.lines()).word_backward((row, column)),
+        Motion::WordEnd => Graphemes::new(editor.lines()).word_end((row, column)),
+        Motion::LineStart => (row, 0),
+        Motion::LineEnd => (row, line_len(editor, row)),
+        Motion::FirstLine => (0, 0),
+        Motion::LastLine => (last_row, 0),
+    }
+}
+
+/// vi's state between key presses: the mode, a partly typed command, and
+/// what is needed for `p` and `.`
+#[derive(Debug, Clone, Default)]
+pub struct ViState {
+    mode: Mode,
+    /// Normal mode keys of a command that isn't complete yet
+    pending: String,
+    /// Keys of the change being made, through to Esc for changes that insert
+    change: Vec<KeyEvent>,
+    /// Keys of the last complete change, for `.`
+    last_change: Vec<KeyEvent>,
+    /// `.` is feeding `last_change` back in
+    replaying: bool,
+    register: Register,
+}
+
+impl ViState {
+    /// Marks the prompt, so the mode is always visible: `:` in normal mode
+    pub fn prompt_mark(&self) -> char {
+        match self.mode {
+            Mode::Insert => '>',
+            Mode::Normal => ':',
+        }
+    }
+
+    fn record(&mut self, event: KeyEvent) {
+        if !self.replaying {
+            self.change.push(event);
+        }
+    }
+
+    fn finish_change(&mut self, editor: &mut LineEditor) {
+        editor.end_undo_group();
+        if !self.replaying {
+            self.last_change = std::mem::take(&mut self.change);
+        }
+    }
+
+    fn insert_key(
+        &mut self,
+        event: KeyEvent,
+        editor: &mut LineEditor,
+        options: InputOptions,
+    ) -> KeyOutcome {
+        if event.code == KeyCode::Esc {
+            self.record(event);
+            self.finish_change(editor);
+            self.mode = Mode::Normal;
+            // As in vi, the cursor steps back onto the last inserted grapheme
+            let (row, column) = editor.cursor();
+            editor.set_cursor((row, column.saturating_sub(1)));
+            return KeyOutcome::Edited;
+        }
+        // Typing before any command, as when input starts, is a change of its
+        // own; it repeats as if it had been entered with `i`
+        if !self.replaying && self.change.is_empty() {
+            editor.begin_undo_group();
+            self.record(KeyEvent::new(KeyCode::Char('i'), KeyModifiers::NONE));
+        }
+        let outcome = stdin_handler::apply_key(event, editor, options);
+        if outcome == KeyOutcome::Edited {
+            self.record(event);
+        }
+        outcome
+    }
+
+    fn normal_key(
+        &mut self,
+        event: KeyEvent,
+        editor: &mut LineEditor,
+        options: InputOptions,
+    ) -> KeyOutcome {
+        let control = event.modifiers.contains(KeyModifiers::CONTROL);
+        let key = match event.code {
+            KeyCode::Char(c) if control => {
+                return match c {
+                    'd' => KeyOutcome::Submit,
+                    'c' => KeyOutcome::Abort,
+                    'z' => KeyOutcome::Suspend,
+                    'l' => KeyOutcome::ClearScreen,
+                    'r' => {
+                        self.pending.clear();
+                        editor.redo();
+                        keep_cursor_on_line(editor);
+                        KeyOutcome::Edited
+                    }
+                    _ => KeyOutcome::Ignored,
+                };
+            }
+            KeyCode::Enter if control => return KeyOutcome::Submit,
+            KeyCode::Esc => {
+                self.pending.clear();
+                return KeyOutcome::Ignored;
+            }
+            KeyCode::Char(c) => c,
+            KeyCode::Left | KeyCode::Backspace => 'h',
+            KeyCode::Right => 'l',
+            KeyCode::Up => 'k',
+            KeyCode::Down | KeyCode::Enter => 'j',
+            KeyCode::Home => '0',
+            KeyCode::End => '$',
+            _ => return KeyOutcome::Ignored,
+        };
+
+        if self.pending.is_empty() && !self.replaying {
+            self.change.clear();
+        }
+        self.record(event);
+        self.pending.push(key);
+        let command = match parse_command(&self.pending) {
+            Parse::Complete(command) => command,
+            Parse::Incomplete => return KeyOutcome::Ignored,
+            Parse::Invalid => {
+                self.pending.clear();
+                return KeyOutcome::Ignored;
+            }
+        };
+        self.pending.clear();
+        self.run(command, editor, options);
+
+        if self.mode == Mode::Normal {
+            keep_cursor_on_line(editor);
+        }
+        KeyOutcome::Edited
+    }
+
+    fn run(&mut self, command: Command, editor: &mut LineEditor, options: InputOptions) {
+        match command {
+            Command::Move(Motion::Up) => editor.move_up(),
+            Command::Move(Motion::Down) => editor.move_down(),
+            Command::Move(motion) => editor.set_cursor(motion_target(motion, editor)),
+            Command::Operate(operator, target) => {
+                if operator != Operator::Yank {
+                    editor.begin_undo_group();
+                }
+                self.operate(operator, target, editor);
+                match operator {
+                    Operator::Change => self.mode = Mode::Insert,
+                    Operator::Delete => self.finish_change(editor),
+                    Operator::Yank => {}
+                }
+            }
+            Command::Insert(entry) => {
+                editor.begin_undo_group();
+                enter_insert(entry, editor);
+                self.mode = Mode::Insert;
+            }
+            Command::Put { before } => {
+                editor.begin_undo_group();
+                self.put(before, editor);
+                self.finish_change(editor);
+            }
+            Command::Undo => {
+                editor.undo();
+            }
+            Command::Repeat => self.repeat(editor, options),
+        }
+    }
+
+    fn operate(&mut self, operator: Operator, target: Target, editor: &mut LineEditor) {
+        let cursor = editor.cursor();
+        let motion = match target {
+            Target::Line => return self.operate_on_lines(operator, cursor.0, cursor.0, editor),
+            Target::Motion(motion) if motion.is_linewise() => {
+                let row = motion_target(motion, editor).0;
+                return self.operate_on_lines(
+                    operator,
+                    cursor.0.min(row),
+                    cursor.0.max(row),
+                    editor,
+                );
+            }
+            // As in vi, `cw` on a word changes just the word, like `ce`
+            Target::Motion(Motion::WordForward)
+                if operator == Operator::Change
+                    && Graphemes::new(editor.lines()).class(cursor) != Class::Blank =>
+            {
+                Motion::WordEnd
+            }
+            Target::Motion(motion) => motion,
+        };
+
+        let mut target = motion_target(motion, editor);
+        if motion == Motion::WordEnd {
+            // `e` includes the grapheme it lands on
+            target.1 = (target.1 + 1).min(line_len(editor, target.0));
+        } else if motion == Motion::WordForward && target.0 > cursor.0 {
+            // `dw` on the last word of a line stops at the line break
+            target = (cursor.0, line_len(editor, cursor.0));
+        }
+        let (start, end) = if target < cursor {
+            (target, cursor)
+        } else {
+            (cursor, target)
+        };
+
+        let text = if operator == Operator::Yank {
+            editor.set_cursor(start);
+            editor.text_range(start, end)
+        } else {
+            editor.delete_range(start, end)
+        };
+        self.register = Register {
+            text,
+            linewise: false,
+        };
+    }
+
+    fn operate_on_lines(
+        &mut self,
+        operator: Operator,
+        first: usize,
+        last: usize,
+        editor: &mut LineEditor,
+    ) {
+        let text = match operator {
+            Operator::Delete => editor.delete_lines(first, last),
+            // The lines are emptied into one for typing their replacement
+            Operator::Change => editor.delete_range((first, 0), (last, line_len(editor, last))),
+            Operator::Yank => {
+                if first != editor.cursor().0 {
+                    editor.set_cursor((first, 0));
+                }
+                editor.lines()[first..=last].join("\n")
+            }
+        };
+        self.register = Register {
+            text,
+            linewise: true,
+        };
+    }
+
+    /// Put the register after the cursor, or before it for `P`; whole lines
+    /// go below or above the current line
+    fn put(&mut self, before: bool, editor: &mut LineEditor) {
+        let Register { text, linewise } = &self.register;
+        if text.is_empty() {
+            return;
+        }
+        let (row, column) = editor.cursor();
+        if *linewise {
+            editor.insert_lines(if before { row } else { row + 1 }, text);
+            return;
+        }
+        if !before && line_len(editor, row) > 0 {
+            editor.set_cursor((row, column + 1));
+        }
+        editor.insert_str(text);
+        let (row, column) = editor.cursor();
+        editor.set_cursor((row, column.saturating_sub(1)));
+    }
+
+    /// Make the last change again at the cursor by replaying its keys
+    fn repeat(&mut self, editor: &mut LineEditor, options: InputOptions) {
+        if self.replaying {
+            return;
+        }
+        self.replaying = true;
+        for event in self.last_change.clone() {
+            apply_key(event, editor, self, options);
+        }
+        self.replaying = false;
+    }
+}
+
+/// Outside insert mode the cursor sits on a grapheme, not after the last
+fn keep_cursor_on_line(editor: &mut LineEditor) {
+    let (row, column) = editor.cursor();
+    let len = line_len(editor, row);
+    if column >= len && len > 0 {
+        editor.set_cursor((row, len - 1));
+    }
+}
+
+fn enter_insert(entry: Entry, editor: &mut LineEditor) {
+    let (row, column) = editor.cursor();
+    match entry {
+        Entry::BeforeCursor => {}
+        Entry::AfterCursor => editor.set_cursor((row, column + 1)),
+        Entry::LineStart => editor.move_to_line_start(),
+        Entry::LineEnd => editor.move_to_line_end(),
+        Entry::LineBelow => editor.insert_lines(row + 1, ""),
+        Entry::LineAbove => editor.insert_lines(row, ""),
+    }
+}
+
+/// Apply a key press with vi bindings, without drawing anything. Ctrl+D,
+/// Ctrl+C, Ctrl+Z and Ctrl+L work in both modes.
+pub fn apply_key(
+    event: KeyEvent,
+    editor: &mut LineEditor,
+    state: &mut ViState,
+    options: InputOptions,
+) -> KeyOutcome {
+    if event.kind == KeyEventKind::Release {
+        return KeyOutcome::Ignored;
+    }
+    match state.mode {
+        Mode::Insert => state.insert_key(event, editor, options),
+        Mode::Normal => state.normal_key(event, editor, options),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    /// Type `keys` with `<Esc>` standing for the Escape key
+    fn type_keys(keys: &str, editor: &mut LineEditor, state: &mut ViState) {
+        let options = InputOptions::default();
+        for (i, part) in keys.split("<Esc>").enumerate() {
+            if i > 0 {
+                apply_key(key(KeyCode::Esc), editor, state, options);
+            }
+            for c in part.chars() {
+                let code = if c == '\n' {
+                    KeyCode::Enter
+                } else {
+                    KeyCode::Char(c)
+                };
+                apply_key(key(code), editor, state, options);
+            }
+        }
+    }
+
+    /// An editor holding `text`, in normal mode at the start of the first line
+    fn normal(text: &str) -> (LineEditor, ViState) {
+        let mut editor = LineEditor::new();
+        let mut state = ViState::default();
+        type_keys(&format!("{text}<Esc>gg0"), &mut editor, &mut state);
+        (editor, state)
+    }
+
+    #[test]
+    fn test_parse_inputrc() {
+        assert_eq!(
+            parse_inputrc("set editing-mode vi\n"),
+            Some(EditingMode::Vi)
+        );
+        assert_eq!(parse_inputrc("  set -o vi"), Some(EditingMode::Vi));
+        assert_eq!(
+            parse_inputrc("set editing-mode vi\nset editing-mode emacs"),
+            Some(EditingMode::Emacs)
+        );
+        assert_eq!(
+            parse_inputrc("# set editing-mode vi\nset bell-style none"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_escape_switches_to_normal_mode() {
+        let mut editor = LineEditor::new();
+        let mut state = ViState::default();
+        type_keys("abc", &mut editor, &mut state);
+        assert_eq!(state.prompt_mark(), '>');
+        type_keys("<Esc>", &mut editor, &mut state);
+        assert_eq!(state.prompt_mark(), ':');
+        assert_eq!(editor.cursor(), (0, 2));
+
+        type_keys("0iX<Esc>Ay", &mut editor, &mut state);
+        assert_eq!(editor.current_line(), "Xabcy");
+    }
+
+    #[test]
+    fn test_motions() {
+        let (mut editor, mut state) = normal("foo.bar  baz\n  qux");
+        let mut expect = |keys: &str, cursor: Position| {
+            type_keys(keys, &mut editor, &mut state);
+            assert_eq!(editor.cursor(), cursor, "after {keys}");
+        };
+        expect("w", (0, 3));
+        expect("w", (0, 4));
+        expect("w", (0, 9));
+        // Words carry on across lines
+        expect("w", (1, 2));
+        expect("b", (0, 9));
+        expect("e", (0, 11));
+        expect("$", (0, 11));
+        expect("0", (0, 0));
+        expect("l", (0, 1));
+        expect("j", (1, 1));
+        expect("h", (1, 0));
+        expect("gg", (0, 0));
+        expect("G", (1, 0));
+        expect("k", (0, 0));
+    }
+
+    #[test]
+    fn test_operators_with_motions() {
+        let (mut editor, mut state) = normal("one two three");
+        type_keys("dw", &mut editor, &mut state);
+        assert_eq!(editor.current_line(), "two three");
+        type_keys("cwsix<Esc>", &mut editor, &mut state);
+        assert_eq!(editor.current_line(), "six three");
+        type_keys("$d0", &mut editor, &mut state);
+        assert_eq!(editor.current_line(), "e");
+
+        let (mut editor, mut state) = normal("one two");
+        type_keys("yeP", &mut editor, &mut state);
+        assert_eq!(editor.current_line(), "oneone two");
+        type_keys("wD", &mut editor, &mut state);
+        assert_eq!(editor.current_line(), "oneone ");
+    }
+
+    #[test]
+    fn test_linewise_operators_and_put() {
+        let (mut editor, mut state) = normal("a\nb\nc\nd");
+        type_keys("jdd", &mut editor, &mut state);
+        assert_eq!(editor.lines(), ["a", "c", "d"]);
+        type_keys("p", &mut editor, &mut state);
+        assert_eq!(editor.lines(), ["a", "c", "b", "d"]);
+        assert_eq!(editor.cursor(), (2, 0));
+
+        type_keys("dk", &mut editor, &mut state);
+        assert_eq!(editor.lines(), ["a", "d"]);
+        type_keys("yyGP", &mut editor, &mut state);
+        assert_eq!(editor.lines(), ["a", "d", "d"]);
+        type_keys("ggdG", &mut editor, &mut state);
+        assert_eq!(editor.lines(), [""]);
+
+        let (mut editor, mut state) = normal("a\nb");
+        type_keys("ccz<Esc>", &mut editor, &mut state);
+        assert_eq!(editor.lines(), ["z", "b"]);
+    }
+
+    #[test]
+    fn test_undo_and_repeat() {
+        let (mut editor, mut state) = normal("one two three");
+        type_keys("dw.", &mut editor, &mut state);
+        assert_eq!(editor.current_line(), "three");
+        type_keys("u", &mut editor, &mut state);
+        assert_eq!(editor.current_line(), "two three");
+
+        // An insert repeats with the text typed
+        type_keys("A!<Esc>.", &mut editor, &mut state);
+        assert_eq!(editor.current_line(), "two three!!");
+        type_keys("uu", &mut editor, &mut state);
+        assert_eq!(editor.current_line(), "two three");
+
+        // Ctrl+R redoes what u undid
+        let ctrl_r = KeyEvent::new(KeyCode::Char('r'), KeyModifiers::CONTROL);
+        apply_key(ctrl_r, &mut editor, &mut state, InputOptions::default());
+        assert_eq!(editor.current_line(), "two three!");
+        type_keys("u", &mut editor, &mut state);
+
+        // Undoing past the start takes back what was typed before Esc
+        type_keys("uuu", &mut editor, &mut state);
+        assert_eq!(editor.current_line(), "");
+    }
+
+    #[test]
+    fn test_repeat_text_typed_at_start() {
+        let mut editor = LineEditor::new();
+        let mut state = ViState::default();
+        type_keys("hello<Esc>.", &mut editor, &mut state);
+        assert_eq!(editor.lines(), ["hellhelloo"]);
+        assert_eq!(state.mode, Mode::Normal);
+    }
+
+    #[test]
+    fn test_open_lines() {
+        let (mut editor, mut state) = normal("b");
+        type_keys("oc<Esc>kOa<Esc>", &mut editor, &mut state);
+        assert_eq!(editor.lines(), ["a", "b", "c"]);
+    }
+}
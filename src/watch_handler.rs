@@ -0,0 +1,286 @@
+use std::{
+    error::Error,
+    io::{self, Write},
+    thread,
+    time::Duration,
+};
+
+use atty::Stream;
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers};
+use regex::Regex;
+
+use crate::{
+    clipboard_handler::{ClipboardProvider, Selection},
+    terminal_guard::TerminalGuard,
+};
+
+/// Turns copied text into the literal that replaces it on the clipboard
+pub type Converter<'a> = dyn Fn(&str) -> Result<String, Box<dyn Error>> + 'a;
+
+/// Tracks the clipboard between polls so every new copy is converted exactly once
+pub struct Watcher {
+    pattern: Option<Regex>,
+    /// Clipboard contents at the previous poll
+    last_seen: Option<String>,
+    /// The last literal quot put on the clipboard itself
+    last_written: Option<String>,
+    /// Copies are left alone while paused
+    active: bool,
+}
+
+impl Watcher {
+    pub fn new(pattern: Option<Regex>) -> Self {
+        Watcher {
+            pattern,
+            last_seen: None,
+            last_written: None,
+            active: true,
+        }
+    }
+
+    /// Pause or resume converting; returns whether the watcher is now active
+    pub fn toggle(&mut self) -> bool {
+        self.active = !self.active;
+        self.active
+    }
+
+    /// Check the clipboard once. Returns the literal written back, if a new copy
+    /// was converted. A copy that fails is not tried again.
+    pub fn poll(
+        &mut self,
+        clipboard: &mut dyn ClipboardProvider,
+        convert: &Converter,
+    ) -> Result<Option<String>, Box<dyn Error>> {
+        // An empty clipboard or one holding non-text data is not an error worth stopping for
+        let Ok(current) = clipboard.read_text(Selection::Clipboard) else {
+            return Ok(None);
+        };
+        if self.last_seen.as_deref() == Some(current.as_str()) {
+            return Ok(None);
+        }
+        // Whatever happens next, this copy has been looked at
+        self.last_seen = Some(current.clone());
+
+        // Our own write coming back around would otherwise be escaped again, forever
+        if !self.active || self.last_written.as_deref() == Some(current.as_str()) {
+            return Ok(None);
+        }
+        if let Some(pattern) = &self.pattern {
+            if !pattern.is_match(&current) {
+                return Ok(None);
+            }
+        }
+
+        let literal = convert(&current)?;
+        clipboard.write_text(&literal, Selection::Clipboard)?;
+        self.last_seen = Some(literal.clone());
+        self.last_written = Some(literal.clone());
+        Ok(Some(literal))
+    }
+}
+
+/// Keep converting new copies until the user quits. In a terminal, Space
+/// pauses and resumes and q, Esc or Ctrl+C quits.
+pub fn run(
+    clipboard: &mut dyn ClipboardProvider,
+    convert: &Converter,
+    pattern: Option<Regex>,
+    interval: Duration,
+) -> Result<(), Box<dyn Error>> {
+    // Each poll would wait on the terminal for the reply, competing with the
+    // key presses read below and stalling the loop where queries are disabled
+    if clipboard.reads_from_terminal() {
+        return Err(format!(
+            "watch cannot read the {} clipboard, which is answered through the terminal; \
+             choose another --clipboard-backend",
+            clipboard.name()
+        )
+        .into());
+    }
+
+    let mut watcher = Watcher::new(pattern);
+    let interactive = atty::is(Stream::Stdin);
+
+    // What is already on the clipboard was copied before we started
+    if let Ok(current) = clipboard.read_text(Selection::Clipboard) {
+        watcher.last_seen = Some(current);
+    }
+
+    // Raw mode lets single key presses through, and is undone however we exit
+    let _terminal = if interactive {
+        Some(TerminalGuard::enter_raw_mode()?)
+    } else {
+        None
+    };
+    print_status(&format!(
+        "Watching the {} clipboard{}",
+        clipboard.name(),
+        if interactive {
+            " (Space: pause/resume, q: quit)"
+        } else {
+            ""
+        }
+    ))?;
+
+    loop {
+        // A copy that fails to convert or write back is skipped, not fatal
+        match watcher.poll(clipboard, convert) {
+            Ok(Some(literal)) => print_status(&format!("Converted: {}", preview(&literal)))?,
+            Ok(None) => {}
+            Err(e) => print_status(&format!("Error: {e}; skipped this copy"))?,
+        }
+
+        if !interactive {
+            thread::sleep(interval);
+            continue;
+        }
+        if !event::poll(interval)? {
+            continue;
+        }
+        if let Event::Key(key_event) = event::read()? {
+            if is_quit_key(key_event) {
+                return Ok(());
+            }
+            if key_event.code == KeyCode::Char(' ') {
+                let status = if watcher.toggle() {
+                    "Resumed"
+                } else {
+                    "Paused"
+                };
+                print_status(status)?;
+            }
+        }
+    }
+}
+
+fn is_quit_key(key_event: KeyEvent) -> bool {
+    match key_event.code {
+        KeyCode::Char('c') => key_event.modifiers.contains(KeyModifiers::CONTROL),
+        KeyCode::Char('q') | KeyCode::Esc => true,
+        _ => false,
+    }
+}
+
+/// Status lines go to stderr, with an explicit carriage return for raw mode
+fn print_status(message: &str) -> io::Result<()> {
+    let mut stderr = io::stderr();
+    write!(stderr, "{message}\r\n")?;
+    stderr.flush()
+}
+
+/// First line of a literal, shortened for the status line
+fn preview(literal: &str) -> String {
+    const MAX_CHARS: usize = 60;
+
+    let first_line = literal.lines().next().unwrap_or_default();
+    if first_line.chars().count() > MAX_CHARS || literal.contains('\n') {
+        let shortened: String = first_line.chars().take(MAX_CHARS).collect();
+        format!("{shortened}…")
+    } else {
+        first_line.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clipboard_handler::MemoryProvider;
+
+    fn quote(text: &str) -> Result<String, Box<dyn Error>> {
+        Ok(format!("{text:?}"))
+    }
+
+    #[test]
+    fn test_new_copy_is_converted_once() {
+        let mut clipboard = MemoryProvider::new("a\"b");
+        let mut watcher = Watcher::new(None);
+
+        let literal = watcher.poll(&mut clipboard, &quote).unwrap();
+        assert_eq!(literal.as_deref(), Some("\"a\\\"b\""));
+        assert_eq!(clipboard.contents(Selection::Clipboard), Some("\"a\\\"b\""));
+
+        // Our own write is not escaped a second time
+        assert_eq!(watcher.poll(&mut clipboard, &quote).unwrap(), None);
+        assert_eq!(clipboard.contents(Selection::Clipboard), Some("\"a\\\"b\""));
+    }
+
+    #[test]
+    fn test_copying_the_same_text_again_converts_it_again() {
+        let mut clipboard = MemoryProvider::new("x");
+        let mut watcher = Watcher::new(None);
+        watcher.poll(&mut clipboard, &quote).unwrap();
+
+        clipboard.write_text("x", Selection::Clipboard).unwrap();
+        let literal = watcher.poll(&mut clipboard, &quote).unwrap();
+        assert_eq!(literal.as_deref(), Some("\"x\""));
+    }
+
+    #[test]
+    fn test_pattern_filter() {
+        let pattern = Regex::new("^SELECT").unwrap();
+        let mut clipboard = MemoryProvider::new("hello");
+        let mut watcher = Watcher::new(Some(pattern));
+
+        assert_eq!(watcher.poll(&mut clipboard, &quote).unwrap(), None);
+        assert_eq!(clipboard.contents(Selection::Clipboard), Some("hello"));
+
+        clipboard
+            .write_text("SELECT 1", Selection::Clipboard)
+            .unwrap();
+        assert!(watcher.poll(&mut clipboard, &quote).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_paused_watcher_leaves_copies_alone() {
+        let mut clipboard = MemoryProvider::new("x");
+        let mut watcher = Watcher::new(None);
+        assert!(!watcher.toggle());
+
+        assert_eq!(watcher.poll(&mut clipboard, &quote).unwrap(), None);
+
+        // Text copied while paused stays as it was after resuming
+        assert!(watcher.toggle());
+        assert_eq!(watcher.poll(&mut clipboard, &quote).unwrap(), None);
+        assert_eq!(clipboard.contents(Selection::Clipboard), Some("x"));
+    }
+
+    #[test]
+    fn test_failed_copy_is_skipped() {
+        let reject_x = |text: &str| {
+            if text == "x" {
+                Err("cannot convert".into())
+            } else {
+                quote(text)
+            }
+        };
+        let mut clipboard = MemoryProvider::new("x");
+        let mut watcher = Watcher::new(None);
+        assert!(watcher.poll(&mut clipboard, &reject_x).is_err());
+        assert_eq!(watcher.poll(&mut clipboard, &reject_x).unwrap(), None);
+
+        clipboard.write_text("y", Selection::Clipboard).unwrap();
+        let literal = watcher.poll(&mut clipboard, &reject_x).unwrap();
+        assert_eq!(literal.as_deref(), Some("\"y\""));
+    }
+
+    #[test]
+    fn test_empty_clipboard_is_skipped() {
+        let mut clipboard = MemoryProvider::default();
+        let mut watcher = Watcher::new(None);
+        assert_eq!(watcher.poll(&mut clipboard, &quote).unwrap(), None);
+    }
+
+    #[test]
+    fn test_terminal_clipboard_is_refused() {
+        let mut clipboard = crate::clipboard_handler::Osc52Provider;
+        let error = run(&mut clipboard, &quote, None, Duration::ZERO).unwrap_err();
+        assert!(error.to_string().contains("osc52"));
+    }
+
+    #[test]
+    fn test_preview() {
+        assert_eq!(preview("\"short\""), "\"short\"");
+        assert_eq!(preview("concat!(\n    \"a\",\n)"), "concat!(…");
+        assert_eq!(preview(&"x".repeat(100)).chars().count(), 61);
+    }
+}